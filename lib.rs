@@ -8,18 +8,81 @@ mod Nft {
     #[cfg(not(feature = "ink-as-dependency"))]
     use ink_prelude::vec::Vec;
 
+    use ink_prelude::collections::BTreeSet;
     use ink_prelude::string::String;
     use ink_storage::collections::{
         hashmap::Entry, HashMap as StorageHashMap, Vec as StorageVec,
     };
     use ink_storage::traits::{PackedLayout, SpreadLayout};
-    use ink_storage::Pack;
+    use ink_storage::{Lazy, Pack};
 
+    use ink_primitives::Key;
+
+    #[cfg(not(test))]
+    use ink_env::call::{build_call, utils::ReturnType};
+    use ink_env::call::{ExecutionInput, Selector};
+    use ink_env::hash::Blake2x256;
     use scale::{Decode, Encode};
 
     /// A token ID.
     pub type TokenId = u32;
 
+    /// A role ID.
+    pub type RoleId = u32;
+
+    /// Administers every role unless another admin role is set.
+    pub const ADMIN_ROLE: RoleId = 0;
+    /// Registers and removes issuers.
+    pub const ISSUER_MANAGER_ROLE: RoleId = 1;
+    /// Pauses and unpauses the contract.
+    pub const PAUSER_ROLE: RoleId = 2;
+    /// Sets and withdraws protocol fees.
+    pub const FEE_MANAGER_ROLE: RoleId = 3;
+    /// Mints into the nft sets whose owner allows minting by the role.
+    pub const MINTER_ROLE: RoleId = 4;
+
+    /// Roles held by the owner of the contract. They move to the new owner when
+    /// ownership changes hands and are revoked when ownership is renounced.
+    pub const OWNER_ROLES: [RoleId; 5] = [
+        ADMIN_ROLE,
+        ISSUER_MANAGER_ROLE,
+        PAUSER_ROLE,
+        FEE_MANAGER_ROLE,
+        MINTER_ROLE,
+    ];
+
+    /// Version of the storage layout of this code. Bump it together with a new
+    /// step in `migrate` whenever an upgrade changes how state is stored.
+    pub const STORAGE_VERSION: u32 = 2;
+
+    /// Storage key the storage version is kept under. It lies outside of the keys of
+    /// the storage struct, so the version can be read whatever layout the stored
+    /// state is in.
+    const STORAGE_VERSION_KEY: [u8; 32] = [0xff; 32];
+
+    /// Maximum number of attributes of a token.
+    pub const MAX_ATTRIBUTES: u32 = 32;
+
+    /// Maximum length in bytes of an attribute key.
+    pub const MAX_ATTRIBUTE_KEY_LEN: usize = 32;
+
+    /// Maximum length in bytes of an attribute value.
+    pub const MAX_ATTRIBUTE_VALUE_LEN: usize = 128;
+
+    /// Time in milliseconds a council proposal can be approved for.
+    pub const PROPOSAL_LIFETIME: Timestamp = 7 * 24 * 60 * 60 * 1000;
+
+    /// Denominator used for royalty and fee rates.
+    pub const MAX_BASIS_POINTS: u16 = 10_000;
+
+    /// Maximum number of entries handled by a single batch call.
+    pub const MAX_BATCH_LEN: u32 = 100;
+
+    /// Selector of `PSP22::transfer`.
+    const PSP22_TRANSFER_SELECTOR: [u8; 4] = [0xdb, 0x20, 0xf9, 0xf5];
+    /// Selector of `PSP22::transfer_from`.
+    const PSP22_TRANSFER_FROM_SELECTOR: [u8; 4] = [0x54, 0xb3, 0xc7, 0x6e];
+
     #[derive(
         Debug, PartialEq, Eq, scale::Encode, scale::Decode, PackedLayout, SpreadLayout,
     )]
@@ -28,10 +91,62 @@ mod Nft {
         pub name: String,
         pub image: String,
         pub has_discount: bool,
+        /// Free-text discount of tokens minted before typed discounts, empty for
+        /// newer tokens whose discount is stored as `DiscountTerms`.
         pub discount: String,
         pub issuer: AccountId,
     }
 
+    /// Discount a token entitles its holder to.
+    #[derive(
+        Debug,
+        PartialEq,
+        Eq,
+        Clone,
+        scale::Encode,
+        scale::Decode,
+        PackedLayout,
+        SpreadLayout,
+    )]
+    #[cfg_attr(feature = "std", derive(::scale_info::TypeInfo))]
+    pub enum Discount {
+        /// Share of the price taken off, in basis points.
+        Percentage { basis_points: u16 },
+        /// Amount taken off the price, in the smallest unit of `currency_code`.
+        FixedAmount {
+            amount: Balance,
+            currency_code: String,
+        },
+        /// `get` more items for free when buying `buy` items.
+        BuyXGetY { buy: u32, get: u32 },
+        /// Item given for free.
+        FreeItem { sku: String },
+        /// Free-text discount of a token minted before typed discounts.
+        Legacy(String),
+    }
+
+    /// Discount of a token together with the time it can be used in.
+    #[derive(
+        Debug,
+        PartialEq,
+        Eq,
+        Clone,
+        scale::Encode,
+        scale::Decode,
+        PackedLayout,
+        SpreadLayout,
+    )]
+    #[cfg_attr(feature = "std", derive(::scale_info::TypeInfo))]
+    pub struct DiscountTerms {
+        pub discount: Discount,
+        /// Timestamp the discount becomes valid at, `None` for right away.
+        pub valid_from: Option<Timestamp>,
+        /// Timestamp the discount stops being valid at, `None` for never.
+        pub valid_until: Option<Timestamp>,
+        /// Number of times the discount can still be redeemed, `None` for no limit.
+        pub remaining_uses: Option<u32>,
+    }
+
     #[derive(
         Debug, PartialEq, Eq, scale::Encode, scale::Decode, PackedLayout, SpreadLayout,
     )]
@@ -50,6 +165,163 @@ mod Nft {
         pub description: String,
         pub status: bool,
         pub owner: AccountId,
+        /// Number of times the name or description was changed.
+        pub revision: u32,
+        pub metadata_frozen: bool,
+        /// Maximum number of tokens in the set, `None` for no limit.
+        pub max_supply: Option<u32>,
+        /// Sealed sets can never be minted into again.
+        pub sealed: bool,
+    }
+
+    /// Metadata given to tokens minted without explicit features.
+    #[derive(
+        Debug,
+        PartialEq,
+        Eq,
+        Clone,
+        scale::Encode,
+        scale::Decode,
+        PackedLayout,
+        SpreadLayout,
+    )]
+    #[cfg_attr(feature = "std", derive(::scale_info::TypeInfo))]
+    pub struct TokenTemplate {
+        pub name: String,
+        pub image: String,
+        pub discount: Option<DiscountTerms>,
+        /// Key/value attributes of the token, such as traits.
+        pub attributes: Vec<(String, String)>,
+    }
+
+    /// Token template as stored before storage version 2.
+    #[derive(
+        Debug, PartialEq, Eq, scale::Encode, scale::Decode, PackedLayout, SpreadLayout,
+    )]
+    #[cfg_attr(feature = "std", derive(::scale_info::TypeInfo))]
+    pub struct LegacyTokenTemplate {
+        pub name: String,
+        pub image: String,
+        pub has_discount: bool,
+        pub discount: String,
+    }
+
+    /// Public sale of an nft set.
+    #[derive(
+        Debug,
+        PartialEq,
+        Eq,
+        Clone,
+        scale::Encode,
+        scale::Decode,
+        PackedLayout,
+        SpreadLayout,
+    )]
+    #[cfg_attr(feature = "std", derive(::scale_info::TypeInfo))]
+    pub struct SaleConfig {
+        /// Price of a single token.
+        pub price: Balance,
+        /// Number of tokens that can be sold.
+        pub max_supply: u32,
+        /// Number of tokens a single account can buy.
+        pub wallet_limit: u32,
+        /// Timestamp the sale opens at.
+        pub start: Timestamp,
+        /// Timestamp the sale closes at.
+        pub end: Timestamp,
+        /// Number of tokens sold so far.
+        pub sold: u32,
+    }
+
+    /// Token an issuer signs off-chain, minted when it is first redeemed.
+    #[derive(Debug, PartialEq, Eq, Clone, scale::Encode, scale::Decode)]
+    #[cfg_attr(feature = "std", derive(::scale_info::TypeInfo))]
+    pub struct Voucher {
+        pub setid: u32,
+        pub id: TokenId,
+        pub metadata: TokenTemplate,
+        /// Price the redeemer pays to the issuer.
+        pub price: Balance,
+        /// Account that can redeem the voucher, or `None` for anyone.
+        pub recipient: Option<AccountId>,
+        /// Timestamp from which the voucher can no longer be redeemed.
+        pub expires_at: Timestamp,
+    }
+
+    /// Administrative action the council executes once enough members approve it.
+    #[derive(
+        Debug,
+        PartialEq,
+        Eq,
+        Clone,
+        scale::Encode,
+        scale::Decode,
+        PackedLayout,
+        SpreadLayout,
+    )]
+    #[cfg_attr(feature = "std", derive(::scale_info::TypeInfo))]
+    pub enum AdminAction {
+        SetIssuer {
+            issuer: AccountId,
+            name: String,
+            status: bool,
+        },
+        RemoveIssuer {
+            issuer: AccountId,
+        },
+        TransferOwnership {
+            new_owner: AccountId,
+        },
+        Pause,
+        Unpause,
+        SetFee {
+            basis_points: u16,
+        },
+        /// Withdraws protocol fees, from the PSP22 contract `currency` if given.
+        WithdrawFees {
+            currency: Option<AccountId>,
+            to: AccountId,
+            amount: Balance,
+        },
+        SetCouncil {
+            members: Vec<AccountId>,
+            threshold: u32,
+        },
+        Upgrade {
+            code_hash: [u8; 32],
+        },
+        GrantRole {
+            role: RoleId,
+            account: AccountId,
+        },
+        RevokeRole {
+            role: RoleId,
+            account: AccountId,
+        },
+        SetRoleAdmin {
+            role: RoleId,
+            admin_role: RoleId,
+        },
+    }
+
+    /// Admin action proposed to the council.
+    #[derive(
+        Debug,
+        PartialEq,
+        Eq,
+        Clone,
+        scale::Encode,
+        scale::Decode,
+        PackedLayout,
+        SpreadLayout,
+    )]
+    #[cfg_attr(feature = "std", derive(::scale_info::TypeInfo))]
+    pub struct Proposal {
+        pub action: AdminAction,
+        pub proposer: AccountId,
+        /// Timestamp after which the proposal can no longer be approved.
+        pub expires_at: Timestamp,
+        pub executed: bool,
     }
 
     // perhaps let anyone with these combination of tokens to start it
@@ -62,6 +334,26 @@ mod Nft {
         pub bids: Vec<u32>,
         pub asks: Vec<u32>,
         pub recepient: AccountId,
+        pub price: Balance,
+        /// PSP22 contract the price is paid in, or `None` for the native currency.
+        pub currency: Option<AccountId>,
+    }
+
+    #[derive(
+        Debug,
+        PartialEq,
+        Eq,
+        Copy,
+        Clone,
+        scale::Encode,
+        scale::Decode,
+        PackedLayout,
+        SpreadLayout,
+    )]
+    #[cfg_attr(feature = "std", derive(::scale_info::TypeInfo))]
+    pub struct RoyaltyInfo {
+        pub receiver: AccountId,
+        pub basis_points: u16,
     }
 
     // nested hashmap
@@ -79,6 +371,10 @@ mod Nft {
         token_features: StorageHashMap<TokenId, TokenFeatures>,
         /// issuer features
         nft_issuer: StorageHashMap<AccountId, IssuerFeatures>,
+        /// Enumeration of the registered issuers
+        issuers: StorageHashMap<u32, AccountId>,
+        issuers_index: StorageHashMap<AccountId, u32>,
+        issuers_count: u32,
         /// Owner of index implementation
         owned_tokens: StorageHashMap<(AccountId, u32), u32>,
         
@@ -94,6 +390,14 @@ mod Nft {
 
         /// Index of owned tokens
         contract_owner: AccountId,
+        /// Account that can accept ownership of the contract.
+        pending_owner: Option<AccountId>,
+        /// Mapping from role and account to membership of the role.
+        roles: StorageHashMap<(RoleId, AccountId), bool>,
+        /// Mapping from role to the role administering it.
+        role_admins: StorageHashMap<RoleId, RoleId>,
+        /// Whether minting, transfers and swaps are stopped.
+        paused: bool,
         nft_set: StorageHashMap<u32, NFTSetFeatures>,
 
         swaps: StorageHashMap<u32, SwapOffer>,
@@ -105,6 +409,97 @@ mod Nft {
         nft_ask_swap: StorageHashMap<(AccountId, u32), u32>,
         nft_ask_swap_index: StorageHashMap<u32, u32>,
         nft_ask_swap_count: StorageHashMap<AccountId, u32>,
+
+        /// Mapping from token to the set it was minted into.
+        token_set: StorageHashMap<TokenId, u32>,
+        /// Royalty paid on every token of a set.
+        set_royalty: StorageHashMap<u32, RoyaltyInfo>,
+        /// Royalty overriding the set royalty for a single token.
+        token_royalty: StorageHashMap<TokenId, RoyaltyInfo>,
+
+        /// Protocol fee in basis points taken from mints and sales.
+        protocol_fee: u16,
+        /// Protocol fees held by the contract until withdrawn.
+        accrued_fees: Balance,
+
+        /// PSP22 contract a set is paid in. Sets without one use the native currency.
+        set_currency: StorageHashMap<u32, AccountId>,
+        /// Protocol fees held by the contract per PSP22 contract.
+        accrued_token_fees: StorageHashMap<AccountId, Balance>,
+
+        /// Token templates stored before storage version 2, moved to `set_templates`
+        /// by `migrate`.
+        legacy_set_templates: StorageHashMap<u32, LegacyTokenTemplate>,
+        /// Public sale of a set.
+        set_sales: StorageHashMap<u32, SaleConfig>,
+        /// Mapping from set and buyer to number of tokens bought in the sale.
+        sale_purchases: StorageHashMap<(u32, AccountId), u32>,
+        /// Enumeration of the accounts allowed to mint into a set besides its owner.
+        set_minters: StorageHashMap<(u32, u32), AccountId>,
+        set_minters_index: StorageHashMap<(u32, AccountId), u32>,
+        set_minters_count: StorageHashMap<u32, u32>,
+
+        /// Lowest token id that may still be free for sequential minting.
+        next_token_id: TokenId,
+
+        /// Merkle root of the accounts allowed to buy from a set before its sale opens.
+        allowlist_roots: StorageHashMap<u32, Hash>,
+        /// Mapping from set and account to number of tokens bought through the allowlist.
+        allowlist_claimed: StorageHashMap<(u32, AccountId), u32>,
+
+        /// Mapping from the hash of a redeemed voucher to its redeemer.
+        redeemed_vouchers: StorageHashMap<Hash, AccountId>,
+
+        /// Storage version 1 kept the version here. It moved to `STORAGE_VERSION_KEY`
+        /// and the field only remains so the fields after it keep their keys.
+        legacy_storage_version: u32,
+
+        // Fields added in storage version 2. Fields are only ever appended, and plain
+        // values are `Lazy` so the state of older versions still loads until
+        // `migrate` initializes them, which it has to do for every field below.
+        /// Enumeration of the council members approving admin actions.
+        council: StorageHashMap<u32, AccountId>,
+        council_index: StorageHashMap<AccountId, u32>,
+        council_count: Lazy<u32>,
+        /// Approvals needed to execute a proposal, 0 while there is no council.
+        council_threshold: Lazy<u32>,
+        /// Admin actions proposed to the council by proposal id.
+        proposals: StorageHashMap<u32, Proposal>,
+        proposals_count: Lazy<u32>,
+        /// Mapping from proposal and council member to approval of the proposal.
+        proposal_approvals: StorageHashMap<(u32, AccountId), bool>,
+        /// Metadata of tokens minted into a set by a sale.
+        set_templates: StorageHashMap<u32, TokenTemplate>,
+        /// Typed discount of a token.
+        token_discounts: StorageHashMap<TokenId, DiscountTerms>,
+        /// Validity window, as start and end timestamps, applying to the discounts of
+        /// every token in a set on top of their own.
+        set_discount_windows: StorageHashMap<u32, (Option<Timestamp>, Option<Timestamp>)>,
+        /// Mapping from token and attribute key to the attribute value.
+        token_attributes: StorageHashMap<(TokenId, String), String>,
+        /// Enumeration of the attribute keys of a token.
+        token_attribute_keys: StorageHashMap<(TokenId, u32), String>,
+        token_attribute_keys_index: StorageHashMap<(TokenId, String), u32>,
+        token_attribute_count: StorageHashMap<TokenId, u32>,
+        /// Tokens whose attributes can no longer be changed.
+        frozen_attributes: StorageHashMap<TokenId, bool>,
+        /// Sets whose tokens can't be transferred.
+        soulbound_sets: StorageHashMap<u32, bool>,
+        /// Tokens that can't be transferred regardless of their set.
+        soulbound_tokens: StorageHashMap<TokenId, bool>,
+        /// Enumeration of the merchants accepting the discounts of an issuer.
+        issuer_merchants: StorageHashMap<(AccountId, u32), AccountId>,
+        issuer_merchants_index: StorageHashMap<(AccountId, AccountId), u32>,
+        issuer_merchants_count: StorageHashMap<AccountId, u32>,
+        /// Number of tokens ever minted into a set, burned tokens included.
+        set_minted: StorageHashMap<u32, u32>,
+        /// Ids of burned tokens, which are never minted again.
+        burned_tokens: StorageHashMap<TokenId, bool>,
+        /// Number of times the discount of a token was redeemed, the nonce merchants
+        /// co-sign.
+        discount_redemptions: StorageHashMap<TokenId, u32>,
+        /// Sets whose owner allows accounts with the minter role to mint into them.
+        role_minting_sets: StorageHashMap<u32, bool>,
     }
 
     #[derive(Encode, Decode, Debug, PartialEq, Eq, Copy, Clone)]
@@ -121,6 +516,57 @@ mod Nft {
         NotIssuer,
         NotContractOwner,
         SetExists,
+        InvalidBasisPoints,
+        IncorrectPayment,
+        TransferFailed,
+        InsufficientFees,
+        CurrencyMismatch,
+        NoTemplate,
+        NoSale,
+        SaleNotActive,
+        SoldOut,
+        WalletLimitReached,
+        InvalidQuantity,
+        InvalidSignature,
+        VoucherRedeemed,
+        InvalidDiscount,
+        NoDiscount,
+        DiscountInactive,
+        DiscountUsedUp,
+        NotMerchant,
+        InvalidAttribute,
+        TooManyAttributes,
+        AttributesFrozen,
+        Soulbound,
+        BatchTooLarge,
+        SetInactive,
+        MetadataFrozen,
+        SetSealed,
+        SupplyExceeded,
+        MissingRole,
+        ContractPaused,
+        MigrationPending,
+        UpgradeFailed,
+        CouncilRequired,
+        NotCouncilMember,
+        InvalidThreshold,
+        ProposalNotFound,
+        ProposalExpired,
+        VoucherExpired,
+        SwapExists,
+        InvalidSaleWindow,
+    }
+
+    /// Errors returned by a PSP22 token contract.
+    #[derive(Encode, Decode, Debug, PartialEq, Eq, Clone)]
+    #[cfg_attr(feature = "std", derive(scale_info::TypeInfo))]
+    pub enum PSP22Error {
+        Custom(String),
+        InsufficientBalance,
+        InsufficientAllowance,
+        ZeroRecipientAddress,
+        ZeroSenderAddress,
+        SafeTransferCheckFailed(String),
     }
 
     /// Event emitted when a token transfer occurs.
@@ -156,215 +602,2237 @@ mod Nft {
         id: TokenId,
     }
 
-    impl Erc721 {
-        /// Creates a new ERC721 token contract.
-        #[ink(constructor)]
-        pub fn new() -> Self {
-            let contract_owner = Self::env().caller();
-
-            Self {
-                token_owner: Default::default(),
-                token_approvals: Default::default(),
-                owned_tokens_count: Default::default(),
-                operator_approvals: Default::default(),
-                token_features: Default::default(),
-                owned_tokens: Default::default(),
-                owned_tokens_index: Default::default(),
-                nft_issuer: Default::default(),
-                contract_owner,
-                nft_set: Default::default(),
-                owned_nft_set_count: Default::default(),
-                owned_nft_set: Default::default(),
-                owned_nft_set_index: Default::default(),
-                owned_set_tokens: Default::default(),
-                owned_set_tokens_index: Default::default(),
-                owned_set_tokens_count: Default::default(),
-                swaps: Default::default(),
-                nft_bid_swap: Default::default(),
-                nft_bid_swap_index: Default::default(),
-                nft_bid_swap_count: Default::default(),
-                nft_ask_swap: Default::default(),
-                nft_ask_swap_index: Default::default(),
-                nft_ask_swap_count: Default::default(),
-            }
-        }
+    /// Event emitted when a royalty is paid out of a sale.
+    #[ink(event)]
+    pub struct RoyaltyPaid {
+        #[ink(topic)]
+        receiver: AccountId,
+        #[ink(topic)]
+        id: TokenId,
+        amount: Balance,
+    }
 
-        /// Returns the balance of the owner.
-        ///
-        /// This represents the amount of unique tokens the owner has.
-        #[ink(message)]
-        pub fn balance_of(&self, owner: AccountId) -> u32 {
-            self.balance_of_or_zero(&owner)
-        }
+    /// Event emitted when the protocol fee is changed.
+    #[ink(event)]
+    pub struct FeeUpdated {
+        old_fee: u16,
+        new_fee: u16,
+    }
 
-        #[ink(message)]
-        pub fn balance_of_bids(&self, owner: AccountId) -> u32 {
-            *self.nft_bid_swap_count.get(&owner).unwrap_or(&0)
-        }
+    /// Event emitted when a protocol fee is kept from a payment.
+    #[ink(event)]
+    pub struct FeeCollected {
+        #[ink(topic)]
+        currency: Option<AccountId>,
+        amount: Balance,
+    }
 
-        #[ink(message)]
-        pub fn bid_of_owner_by_index(&self, owner: AccountId, index: u32) -> u32 {
-            *self.nft_bid_swap.get(&(owner, index)).unwrap_or(&0)
-        }
+    /// Event emitted when accrued protocol fees are withdrawn.
+    #[ink(event)]
+    pub struct FeesWithdrawn {
+        #[ink(topic)]
+        currency: Option<AccountId>,
+        #[ink(topic)]
+        to: AccountId,
+        amount: Balance,
+    }
 
-        #[ink(message)]
-        pub fn balance_of_asks(&self, owner: AccountId) -> u32 {
-            *self.nft_ask_swap_count.get(&owner).unwrap_or(&0)
-        }
+    /// Event emitted when a voucher is redeemed.
+    #[ink(event)]
+    pub struct VoucherRedeemed {
+        #[ink(topic)]
+        voucher: Hash,
+        #[ink(topic)]
+        redeemer: AccountId,
+        id: TokenId,
+    }
 
-        #[ink(message)]
-        pub fn ask_of_owner_by_index(&self, owner: AccountId, index: u32) -> u32 {
-            *self.nft_ask_swap.get(&(owner, index)).unwrap_or(&0)
-        }
+    /// Event emitted when an account is granted a role.
+    #[ink(event)]
+    pub struct RoleGranted {
+        #[ink(topic)]
+        role: RoleId,
+        #[ink(topic)]
+        account: AccountId,
+        #[ink(topic)]
+        sender: AccountId,
+    }
 
+    /// Event emitted when an account loses a role.
+    #[ink(event)]
+    pub struct RoleRevoked {
+        #[ink(topic)]
+        role: RoleId,
+        #[ink(topic)]
+        account: AccountId,
+        #[ink(topic)]
+        sender: AccountId,
+    }
 
-        #[ink(message)]
-        pub fn get_swap(
-            &self,
-            swapid: u32,
-        ) -> (
-            Option<AccountId>,
-            Option<AccountId>,
-            Option<Vec<u32>>,
-            Option<Vec<u32>>,
-        ) {
-            let maker = self.swaps.get(&swapid).map(|v| v.maker.clone());
-            let bids = self.swaps.get(&swapid).map(|v| v.bids.clone());
+    /// Event emitted when the role administering a role changes.
+    #[ink(event)]
+    pub struct RoleAdminChanged {
+        #[ink(topic)]
+        role: RoleId,
+        previous_admin_role: RoleId,
+        new_admin_role: RoleId,
+    }
 
-            let asks = self.swaps.get(&swapid).map(|v| v.asks.clone());
-            let recepient = self.swaps.get(&swapid).map(|v| v.recepient.clone());
-            return (recepient, maker, bids, asks);
-        }
+    /// Event emitted when the contract is paused.
+    #[ink(event)]
+    pub struct Paused {
+        #[ink(topic)]
+        account: AccountId,
+    }
 
-        // get the swaps of owner by index
+    /// Event emitted when the contract is unpaused.
+    #[ink(event)]
+    pub struct Unpaused {
+        #[ink(topic)]
+        account: AccountId,
+    }
 
-        #[ink(message)]
-        pub fn get_token(
-            &self,
-            id: TokenId,
-        ) -> (
-            Option<String>,
-            Option<String>,
-            Option<String>,
-            Option<bool>,
-            Option<AccountId>,
-        ) {
-            let name = self.token_features.get(&id).map(|v| v.name.clone());
-            let image = self.token_features.get(&id).map(|v| v.image.clone());
-            let discount = self.token_features.get(&id).map(|v| v.discount.clone());
-            let has_discount =
-                self.token_features.get(&id).map(|v| v.has_discount.clone());
-            let issuer = self.token_features.get(&id).map(|v| v.issuer.clone());
-            return (name, image, discount, has_discount, issuer);
-        }
+    /// Event emitted when the council members or threshold change.
+    #[ink(event)]
+    pub struct CouncilChanged {
+        members: Vec<AccountId>,
+        threshold: u32,
+    }
 
-        #[ink(message)]
-        pub fn token_of_owner_by_index(&self, owner: AccountId, index: u32) -> u32 {
-            *self.owned_tokens.get(&(owner, index)).unwrap_or(&0)
-        }
+    /// Event emitted when an admin action is proposed to the council.
+    #[ink(event)]
+    pub struct ProposalCreated {
+        #[ink(topic)]
+        id: u32,
+        #[ink(topic)]
+        proposer: AccountId,
+        expires_at: Timestamp,
+    }
 
-        /// Returns the owner of the token.
-        #[ink(message)]
-        pub fn owner_of(&self, id: TokenId) -> Option<AccountId> {
-            self.token_owner.get(&id).cloned()
-        }
+    /// Event emitted when a council member approves a proposal.
+    #[ink(event)]
+    pub struct ProposalApproved {
+        #[ink(topic)]
+        id: u32,
+        #[ink(topic)]
+        approver: AccountId,
+    }
 
-        // The Issuer Set Balance
-        #[ink(message)]
-        pub fn issuer_set_balance(&self, of: AccountId) -> u32 {
-            *self.owned_nft_set_count.get(&of).unwrap_or(&0)
-        }
+    /// Event emitted when a proposal is executed.
+    #[ink(event)]
+    pub struct ProposalExecuted {
+        #[ink(topic)]
+        id: u32,
+    }
 
-        // Enumerate Issuer Set Balance
+    /// Event emitted when the code of the contract is replaced.
+    #[ink(event)]
+    pub struct Upgraded {
+        #[ink(topic)]
+        code_hash: Hash,
+    }
 
-        #[ink(message)]
-        pub fn set_of_owner_by_index(&self, owner: AccountId, index: u32) -> u32 {
-            *self.owned_nft_set.get(&(owner, index)).unwrap_or(&0)
-        }
+    /// Event emitted when the stored state is migrated to a new layout.
+    #[ink(event)]
+    pub struct Migrated {
+        from_version: u32,
+        to_version: u32,
+    }
 
-        // the balance of nft tokens
-        #[ink(message)]
-        pub fn nft_set_balance(&self, setId: u32) -> u32 {
-            *self.owned_set_tokens_count.get(&setId).unwrap_or(&0)
-        }
+    /// Event emitted when a new owner of the contract is proposed.
+    #[ink(event)]
+    pub struct OwnershipTransferStarted {
+        #[ink(topic)]
+        previous_owner: AccountId,
+        #[ink(topic)]
+        new_owner: AccountId,
+    }
 
-        // the balance of nft tokens
-        #[ink(message)]
-        pub fn nft_by_set_index(&self, setId: u32, index: u32) -> u32 {
-            *self.owned_set_tokens.get(&(setId, index)).unwrap_or(&0)
-        }
+    /// Event emitted when ownership of the contract changes.
+    #[ink(event)]
+    pub struct OwnershipTransferred {
+        #[ink(topic)]
+        previous_owner: Option<AccountId>,
+        #[ink(topic)]
+        new_owner: Option<AccountId>,
+    }
 
-        #[ink(message)]
-        pub fn get_nft_set(&self, id: TokenId) -> (Option<String>, Option<String>) {
-            let name = self.nft_set.get(&id).map(|v| v.name.clone());
-            let description = self.nft_set.get(&id).map(|v| v.description.clone());
-            return (name, description);
-        }
+    /// Event emitted when an issuer is registered.
+    #[ink(event)]
+    pub struct IssuerAdded {
+        #[ink(topic)]
+        issuer: AccountId,
+        name: String,
+        status: bool,
+    }
 
-        #[ink(message)]
-        pub fn issuer_name(&self, to: AccountId) -> Option<String> {
-            self.nft_issuer.get(&to).map(|v| v.name.clone())
-        }
+    /// Event emitted when a registered issuer is changed.
+    #[ink(event)]
+    pub struct IssuerUpdated {
+        #[ink(topic)]
+        issuer: AccountId,
+        name: String,
+        status: bool,
+    }
 
-        #[ink(message)]
+    /// Event emitted when an issuer is removed from the registry.
+    #[ink(event)]
+    pub struct IssuerRemoved {
+        #[ink(topic)]
+        issuer: AccountId,
+    }
+
+    /// Event emitted when an issuer is activated or deactivated.
+    #[ink(event)]
+    pub struct IssuerStatusChanged {
+        #[ink(topic)]
+        issuer: AccountId,
+        status: bool,
+    }
+
+    /// Event emitted when an nft set is activated or deactivated.
+    #[ink(event)]
+    pub struct SetStatusChanged {
+        #[ink(topic)]
+        setid: u32,
+        status: bool,
+    }
+
+    /// Event emitted when an nft set is transferred to another issuer.
+    #[ink(event)]
+    pub struct SetTransferred {
+        #[ink(topic)]
+        from: AccountId,
+        #[ink(topic)]
+        to: AccountId,
+        #[ink(topic)]
+        setid: u32,
+    }
+
+    /// Event emitted when the name or description of an nft set changes.
+    #[ink(event)]
+    pub struct SetMetadataUpdated {
+        #[ink(topic)]
+        setid: u32,
+        revision: u32,
+        old_name: String,
+        new_name: String,
+        old_description: String,
+        new_description: String,
+    }
+
+    /// Event emitted when the metadata of an nft set is frozen.
+    #[ink(event)]
+    pub struct SetMetadataFrozen {
+        #[ink(topic)]
+        setid: u32,
+    }
+
+    /// Event emitted when the maximum supply of an nft set changes.
+    #[ink(event)]
+    pub struct MaxSupplyChanged {
+        #[ink(topic)]
+        setid: u32,
+        max_supply: Option<u32>,
+    }
+
+    /// Event emitted when an nft set is sealed.
+    #[ink(event)]
+    pub struct SetSealed {
+        #[ink(topic)]
+        setid: u32,
+    }
+
+    /// Event emitted when an issuer registers a merchant accepting its discounts.
+    #[ink(event)]
+    pub struct MerchantAdded {
+        #[ink(topic)]
+        issuer: AccountId,
+        #[ink(topic)]
+        merchant: AccountId,
+    }
+
+    /// Event emitted when an issuer removes a merchant.
+    #[ink(event)]
+    pub struct MerchantRemoved {
+        #[ink(topic)]
+        issuer: AccountId,
+        #[ink(topic)]
+        merchant: AccountId,
+    }
+
+    /// Event emitted when the discount of a token is used at a merchant.
+    #[ink(event)]
+    pub struct DiscountRedeemed {
+        #[ink(topic)]
+        id: TokenId,
+        #[ink(topic)]
+        merchant: AccountId,
+        #[ink(topic)]
+        holder: AccountId,
+        remaining_uses: Option<u32>,
+    }
+
+    /// Event emitted when the issuer of a token moves the end of its discount.
+    #[ink(event)]
+    pub struct DiscountExtended {
+        #[ink(topic)]
+        id: TokenId,
+        valid_until: Option<Timestamp>,
+    }
+
+    /// Event emitted when the issuer of a token takes its discount away.
+    #[ink(event)]
+    pub struct DiscountRevoked {
+        #[ink(topic)]
+        id: TokenId,
+    }
+
+    /// Event emitted when the discount window of an nft set changes.
+    #[ink(event)]
+    pub struct SetDiscountWindowChanged {
+        #[ink(topic)]
+        setid: u32,
+        valid_from: Option<Timestamp>,
+        valid_until: Option<Timestamp>,
+    }
+
+    /// Event emitted when an attribute of a token is added or changed.
+    #[ink(event)]
+    pub struct AttributeSet {
+        #[ink(topic)]
+        id: TokenId,
+        #[ink(topic)]
+        key: String,
+        value: String,
+    }
+
+    /// Event emitted when an attribute of a token is removed.
+    #[ink(event)]
+    pub struct AttributeRemoved {
+        #[ink(topic)]
+        id: TokenId,
+        #[ink(topic)]
+        key: String,
+    }
+
+    /// Event emitted when the attributes of a token are frozen.
+    #[ink(event)]
+    pub struct AttributesFrozen {
+        #[ink(topic)]
+        id: TokenId,
+    }
+
+    /// Event emitted when the tokens of an nft set become transferable or not.
+    #[ink(event)]
+    pub struct SetSoulboundChanged {
+        #[ink(topic)]
+        setid: u32,
+        soulbound: bool,
+    }
+
+    /// Event emitted when a token becomes transferable or not.
+    #[ink(event)]
+    pub struct TokenSoulboundChanged {
+        #[ink(topic)]
+        id: TokenId,
+        soulbound: bool,
+    }
+
+    /// Event emitted when the issuer of a soulbound token burns it.
+    #[ink(event)]
+    pub struct TokenRevoked {
+        #[ink(topic)]
+        id: TokenId,
+        #[ink(topic)]
+        issuer: AccountId,
+        #[ink(topic)]
+        holder: AccountId,
+    }
+
+    /// Event emitted when an account is allowed to mint into an nft set.
+    #[ink(event)]
+    pub struct MinterGranted {
+        #[ink(topic)]
+        setid: u32,
+        #[ink(topic)]
+        minter: AccountId,
+    }
+
+    /// Event emitted when an account is no longer allowed to mint into an nft set.
+    #[ink(event)]
+    pub struct MinterRevoked {
+        #[ink(topic)]
+        setid: u32,
+        #[ink(topic)]
+        minter: AccountId,
+    }
+
+    /// Event emitted when accounts with the minter role become allowed to mint into
+    /// an nft set or not.
+    #[ink(event)]
+    pub struct RoleMintingChanged {
+        #[ink(topic)]
+        setid: u32,
+        allowed: bool,
+    }
+
+    impl Erc721 {
+        /// Creates a new ERC721 token contract.
+        #[ink(constructor)]
+        pub fn new() -> Self {
+            let contract_owner = Self::env().caller();
+
+            let mut contract = Self {
+                token_owner: Default::default(),
+                token_approvals: Default::default(),
+                owned_tokens_count: Default::default(),
+                operator_approvals: Default::default(),
+                token_features: Default::default(),
+                token_discounts: Default::default(),
+                set_discount_windows: Default::default(),
+                token_attributes: Default::default(),
+                token_attribute_keys: Default::default(),
+                token_attribute_keys_index: Default::default(),
+                token_attribute_count: Default::default(),
+                frozen_attributes: Default::default(),
+                soulbound_sets: Default::default(),
+                soulbound_tokens: Default::default(),
+                owned_tokens: Default::default(),
+                owned_tokens_index: Default::default(),
+                nft_issuer: Default::default(),
+                issuers: Default::default(),
+                issuers_index: Default::default(),
+                issuers_count: 0,
+                contract_owner,
+                pending_owner: None,
+                roles: Default::default(),
+                role_admins: Default::default(),
+                paused: false,
+                council: Default::default(),
+                council_index: Default::default(),
+                council_count: Lazy::new(0),
+                council_threshold: Lazy::new(0),
+                proposals: Default::default(),
+                proposals_count: Lazy::new(0),
+                proposal_approvals: Default::default(),
+                nft_set: Default::default(),
+                owned_nft_set_count: Default::default(),
+                owned_nft_set: Default::default(),
+                owned_nft_set_index: Default::default(),
+                owned_set_tokens: Default::default(),
+                owned_set_tokens_index: Default::default(),
+                owned_set_tokens_count: Default::default(),
+                swaps: Default::default(),
+                nft_bid_swap: Default::default(),
+                nft_bid_swap_index: Default::default(),
+                nft_bid_swap_count: Default::default(),
+                nft_ask_swap: Default::default(),
+                nft_ask_swap_index: Default::default(),
+                nft_ask_swap_count: Default::default(),
+                token_set: Default::default(),
+                set_royalty: Default::default(),
+                token_royalty: Default::default(),
+                protocol_fee: 0,
+                accrued_fees: 0,
+                set_currency: Default::default(),
+                accrued_token_fees: Default::default(),
+                legacy_set_templates: Default::default(),
+                set_templates: Default::default(),
+                set_sales: Default::default(),
+                sale_purchases: Default::default(),
+                set_minters: Default::default(),
+                set_minters_index: Default::default(),
+                set_minters_count: Default::default(),
+                next_token_id: 1,
+                allowlist_roots: Default::default(),
+                allowlist_claimed: Default::default(),
+                redeemed_vouchers: Default::default(),
+                issuer_merchants: Default::default(),
+                issuer_merchants_index: Default::default(),
+                issuer_merchants_count: Default::default(),
+                set_minted: Default::default(),
+                burned_tokens: Default::default(),
+                discount_redemptions: Default::default(),
+                role_minting_sets: Default::default(),
+                legacy_storage_version: 0,
+            };
+            write_storage_version(STORAGE_VERSION);
+            for role in OWNER_ROLES.iter() {
+                contract.roles.insert((*role, contract_owner), true);
+            }
+            contract
+        }
+
+        /// Returns the balance of the owner.
+        ///
+        /// This represents the amount of unique tokens the owner has.
+        #[ink(message)]
+        pub fn balance_of(&self, owner: AccountId) -> u32 {
+            self.balance_of_or_zero(&owner)
+        }
+
+        #[ink(message)]
+        pub fn balance_of_bids(&self, owner: AccountId) -> u32 {
+            *self.nft_bid_swap_count.get(&owner).unwrap_or(&0)
+        }
+
+        #[ink(message)]
+        pub fn bid_of_owner_by_index(&self, owner: AccountId, index: u32) -> u32 {
+            *self.nft_bid_swap.get(&(owner, index)).unwrap_or(&0)
+        }
+
+        #[ink(message)]
+        pub fn balance_of_asks(&self, owner: AccountId) -> u32 {
+            *self.nft_ask_swap_count.get(&owner).unwrap_or(&0)
+        }
+
+        #[ink(message)]
+        pub fn ask_of_owner_by_index(&self, owner: AccountId, index: u32) -> u32 {
+            *self.nft_ask_swap.get(&(owner, index)).unwrap_or(&0)
+        }
+
+
+        #[ink(message)]
+        pub fn get_swap(
+            &self,
+            swapid: u32,
+        ) -> (
+            Option<AccountId>,
+            Option<AccountId>,
+            Option<Vec<u32>>,
+            Option<Vec<u32>>,
+            Option<Balance>,
+        ) {
+            let maker = self.swaps.get(&swapid).map(|v| v.maker.clone());
+            let bids = self.swaps.get(&swapid).map(|v| v.bids.clone());
+
+            let asks = self.swaps.get(&swapid).map(|v| v.asks.clone());
+            let recepient = self.swaps.get(&swapid).map(|v| v.recepient.clone());
+            let price = self.swaps.get(&swapid).map(|v| v.price);
+            return (recepient, maker, bids, asks, price);
+        }
+
+        // get the swaps of owner by index
+
+        #[ink(message)]
+        pub fn get_token(
+            &self,
+            id: TokenId,
+        ) -> (
+            Option<String>,
+            Option<String>,
+            Option<String>,
+            Option<bool>,
+            Option<AccountId>,
+        ) {
+            let name = self.token_features.get(&id).map(|v| v.name.clone());
+            let image = self.token_features.get(&id).map(|v| v.image.clone());
+            let discount = self.token_features.get(&id).map(|v| v.discount.clone());
+            let has_discount =
+                self.token_features.get(&id).map(|v| v.has_discount.clone());
+            let issuer = self.token_features.get(&id).map(|v| v.issuer.clone());
+            return (name, image, discount, has_discount, issuer);
+        }
+
+        /// Returns the discount of token `id`. Free-text discounts of tokens minted
+        /// before typed discounts are returned as `Discount::Legacy` and can be
+        /// redeemed once.
+        #[ink(message)]
+        pub fn discount_of(&self, id: TokenId) -> Option<DiscountTerms> {
+            if let Some(terms) = self.token_discounts.get(&id) {
+                return Some(terms.clone());
+            }
+            let features = self.token_features.get(&id)?;
+            if !features.has_discount {
+                return None;
+            }
+            Some(DiscountTerms {
+                discount: Discount::Legacy(features.discount.clone()),
+                valid_from: None,
+                valid_until: None,
+                remaining_uses: Some(1),
+            })
+        }
+
+        /// Returns the number of times the discount of token `id` was redeemed, which
+        /// merchants co-sign as the nonce of the next redemption.
+        #[ink(message)]
+        pub fn discount_nonce(&self, id: TokenId) -> u32 {
+            *self.discount_redemptions.get(&id).unwrap_or(&0)
+        }
+
+        /// Returns true if the discount of token `id` can be redeemed right now, which
+        /// requires both the token and its set to be in their validity window and the
+        /// discount to have uses left.
+        #[ink(message)]
+        pub fn is_discount_active(&self, id: TokenId) -> bool {
+            match self.discount_of(id) {
+                Some(terms) => {
+                    terms.remaining_uses != Some(0)
+                        && self.is_discount_in_window(id, &terms)
+                }
+                None => false,
+            }
+        }
+
+        /// Returns the start and end of the discount window of the set.
+        #[ink(message)]
+        pub fn discount_window(
+            &self,
+            setid: u32,
+        ) -> Option<(Option<Timestamp>, Option<Timestamp>)> {
+            self.set_discount_windows.get(&setid).cloned()
+        }
+
+        /// Returns the value of the attribute `key` of token `id`.
+        #[ink(message)]
+        pub fn get_attribute(&self, id: TokenId, key: String) -> Option<String> {
+            self.token_attributes.get(&(id, key)).cloned()
+        }
+
+        /// Returns every attribute of token `id` as key/value pairs.
+        #[ink(message)]
+        pub fn attributes_of(&self, id: TokenId) -> Vec<(String, String)> {
+            let count = *self.token_attribute_count.get(&id).unwrap_or(&0);
+            (0..count)
+                .filter_map(|index| self.token_attribute_keys.get(&(id, index)))
+                .filter_map(|key| {
+                    self.token_attributes
+                        .get(&(id, key.clone()))
+                        .map(|value| (key.clone(), value.clone()))
+                })
+                .collect()
+        }
+
+        /// Returns true if the attributes of token `id` can no longer be changed.
+        #[ink(message)]
+        pub fn attributes_frozen(&self, id: TokenId) -> bool {
+            self.frozen_attributes.contains_key(&id)
+        }
+
+        /// Returns true if the tokens of the set can't be transferred.
+        #[ink(message)]
+        pub fn nft_set_soulbound(&self, setid: u32) -> bool {
+            self.soulbound_sets.contains_key(&setid)
+        }
+
+        /// Returns true if token `id` can't be transferred, either by itself or
+        /// because of its set.
+        #[ink(message)]
+        pub fn is_soulbound(&self, id: TokenId) -> bool {
+            self.soulbound_tokens.contains_key(&id)
+                || self
+                    .token_set
+                    .get(&id)
+                    .map_or(false, |setid| self.nft_set_soulbound(*setid))
+        }
+
+        #[ink(message)]
+        pub fn token_of_owner_by_index(&self, owner: AccountId, index: u32) -> u32 {
+            *self.owned_tokens.get(&(owner, index)).unwrap_or(&0)
+        }
+
+        /// Returns the owner of the token.
+        #[ink(message)]
+        pub fn owner_of(&self, id: TokenId) -> Option<AccountId> {
+            self.token_owner.get(&id).cloned()
+        }
+
+        // The Issuer Set Balance
+        #[ink(message)]
+        pub fn issuer_set_balance(&self, of: AccountId) -> u32 {
+            *self.owned_nft_set_count.get(&of).unwrap_or(&0)
+        }
+
+        // Enumerate Issuer Set Balance
+
+        #[ink(message)]
+        pub fn set_of_owner_by_index(&self, owner: AccountId, index: u32) -> u32 {
+            *self.owned_nft_set.get(&(owner, index)).unwrap_or(&0)
+        }
+
+        // the balance of nft tokens
+        #[ink(message)]
+        pub fn nft_set_balance(&self, setId: u32) -> u32 {
+            *self.owned_set_tokens_count.get(&setId).unwrap_or(&0)
+        }
+
+        /// Returns the number of tokens ever minted into the set, which counts
+        /// against its maximum supply even once they are burned.
+        #[ink(message)]
+        pub fn nft_set_minted(&self, setid: u32) -> u32 {
+            *self.set_minted.get(&setid).unwrap_or(&0)
+        }
+
+        // the balance of nft tokens
+        #[ink(message)]
+        pub fn nft_by_set_index(&self, setId: u32, index: u32) -> u32 {
+            *self.owned_set_tokens.get(&(setId, index)).unwrap_or(&0)
+        }
+
+        #[ink(message)]
+        pub fn get_nft_set(
+            &self,
+            id: TokenId,
+        ) -> (Option<String>, Option<String>, Option<u32>, bool) {
+            let name = self.nft_set.get(&id).map(|v| v.name.clone());
+            let description = self.nft_set.get(&id).map(|v| v.description.clone());
+            let max_supply = self.nft_set.get(&id).and_then(|v| v.max_supply);
+            let sealed = self.nft_set.get(&id).map(|v| v.sealed).unwrap_or(false);
+            return (name, description, max_supply, sealed);
+        }
+
+        /// Returns the receiver and amount of royalty owed when token `id` is sold
+        /// for `sale_price`. A token royalty takes precedence over its set royalty.
+        #[ink(message)]
+        pub fn royalty_info(
+            &self,
+            id: TokenId,
+            sale_price: Balance,
+        ) -> Option<(AccountId, Balance)> {
+            let royalty = match self.token_royalty.get(&id) {
+                Some(royalty) => *royalty,
+                None => {
+                    let setid = self.token_set.get(&id)?;
+                    *self.set_royalty.get(setid)?
+                }
+            };
+            Some((
+                royalty.receiver,
+                basis_points_of(sale_price, royalty.basis_points),
+            ))
+        }
+
+        /// Returns the protocol fee in basis points.
+        #[ink(message)]
+        pub fn protocol_fee(&self) -> u16 {
+            self.protocol_fee
+        }
+
+        /// Returns the protocol fees that have not been withdrawn yet.
+        #[ink(message)]
+        pub fn accrued_fees(&self) -> Balance {
+            self.accrued_fees
+        }
+
+        /// Returns the protocol fees accrued in the PSP22 contract `currency`.
+        #[ink(message)]
+        pub fn accrued_token_fees(&self, currency: AccountId) -> Balance {
+            *self.accrued_token_fees.get(&currency).unwrap_or(&0)
+        }
+
+        /// Returns the PSP22 contract the set is paid in, `None` for the native currency.
+        #[ink(message)]
+        pub fn payment_currency(&self, setid: u32) -> Option<AccountId> {
+            self.set_currency.get(&setid).cloned()
+        }
+
+        /// Returns the currency the price of a swap proposal is paid in.
+        #[ink(message)]
+        pub fn swap_currency(&self, swapid: u32) -> Option<AccountId> {
+            self.swaps.get(&swapid).and_then(|v| v.currency)
+        }
+
+        /// Returns the metadata tokens minted into the set by a sale receive.
+        #[ink(message)]
+        pub fn token_template(&self, setid: u32) -> Option<TokenTemplate> {
+            self.set_templates.get(&setid).cloned()
+        }
+
+        /// Returns the public sale of the set.
+        #[ink(message)]
+        pub fn get_sale(&self, setid: u32) -> Option<SaleConfig> {
+            self.set_sales.get(&setid).cloned()
+        }
+
+        /// Returns the Merkle root of the allowlist of the set.
+        #[ink(message)]
+        pub fn allowlist_root(&self, setid: u32) -> Option<Hash> {
+            self.allowlist_roots.get(&setid).cloned()
+        }
+
+        /// Returns the number of tokens `account` bought through the allowlist of the set.
+        #[ink(message)]
+        pub fn allowlist_claimed(&self, setid: u32, account: AccountId) -> u32 {
+            *self.allowlist_claimed.get(&(setid, account)).unwrap_or(&0)
+        }
+
+        /// Returns the account that redeemed the voucher with blake2 hash `voucher`.
+        #[ink(message)]
+        pub fn voucher_redeemer(&self, voucher: Hash) -> Option<AccountId> {
+            self.redeemed_vouchers.get(&voucher).cloned()
+        }
+
+        /// Returns the number of tokens `buyer` bought in the sale of the set.
+        #[ink(message)]
+        pub fn purchased(&self, setid: u32, buyer: AccountId) -> u32 {
+            *self.sale_purchases.get(&(setid, buyer)).unwrap_or(&0)
+        }
+
+        /// Returns the number of times the metadata of the set was changed, and whether
+        /// it is frozen.
+        #[ink(message)]
+        pub fn nft_set_revision(&self, setid: u32) -> Option<(u32, bool)> {
+            self.nft_set
+                .get(&setid)
+                .map(|v| (v.revision, v.metadata_frozen))
+        }
+
+        /// Returns the number of minters of the set.
+        #[ink(message)]
+        pub fn set_minter_balance(&self, setid: u32) -> u32 {
+            *self.set_minters_count.get(&setid).unwrap_or(&0)
+        }
+
+        /// Enumerate the minters of the set.
+        #[ink(message)]
+        pub fn minter_of_set_by_index(
+            &self,
+            setid: u32,
+            index: u32,
+        ) -> Option<AccountId> {
+            self.set_minters.get(&(setid, index)).cloned()
+        }
+
+        /// Returns true if `account` is allowed to mint into the set on behalf of its owner.
+        #[ink(message)]
+        pub fn is_set_minter(&self, setid: u32, account: AccountId) -> bool {
+            self.set_minters_index.contains_key(&(setid, account))
+        }
+
+        /// Returns true if accounts with the minter role can mint into the set.
+        #[ink(message)]
+        pub fn role_minting(&self, setid: u32) -> bool {
+            self.role_minting_sets.contains_key(&setid)
+        }
+
+        /// Returns the number of merchants accepting the discounts of the issuer.
+        #[ink(message)]
+        pub fn merchant_balance(&self, issuer: AccountId) -> u32 {
+            *self.issuer_merchants_count.get(&issuer).unwrap_or(&0)
+        }
+
+        /// Enumerate the merchants of the issuer.
+        #[ink(message)]
+        pub fn merchant_of_issuer_by_index(
+            &self,
+            issuer: AccountId,
+            index: u32,
+        ) -> Option<AccountId> {
+            self.issuer_merchants.get(&(issuer, index)).cloned()
+        }
+
+        /// Returns true if `merchant` accepts the discounts of the issuer.
+        #[ink(message)]
+        pub fn is_merchant(&self, issuer: AccountId, merchant: AccountId) -> bool {
+            self.issuer_merchants_index
+                .contains_key(&(issuer, merchant))
+        }
+
+        /// Returns whether tokens can be minted into the set.
+        #[ink(message)]
+        pub fn nft_set_status(&self, setid: u32) -> Option<bool> {
+            self.nft_set.get(&setid).map(|v| v.status)
+        }
+
+        #[ink(message)]
+        pub fn issuer_name(&self, to: AccountId) -> Option<String> {
+            self.nft_issuer.get(&to).map(|v| v.name.clone())
+        }
+
+        #[ink(message)]
         pub fn is_issuer(&self, to: AccountId) -> Option<bool> {
             self.nft_issuer.get(&to).map(|v| v.status.clone())
         }
 
-        // #[ink(message)]
-        // pub fn swap_status(&self, swapid: u32) -> Option<bool>{
-        // self.swaps.get(&swapid).map(|v| v.status.clone())
-        // }
+        /// Returns the number of registered issuers.
+        #[ink(message)]
+        pub fn issuer_count(&self) -> u32 {
+            self.issuers_count
+        }
+
+        /// Enumerate the registered issuers.
+        #[ink(message)]
+        pub fn issuer_by_index(&self, index: u32) -> Option<AccountId> {
+            self.issuers.get(&index).cloned()
+        }
+
+        // #[ink(message)]
+        // pub fn swap_status(&self, swapid: u32) -> Option<bool>{
+        // self.swaps.get(&swapid).map(|v| v.status.clone())
+        // }
+
+        /// Returns true if `account` has `role`.
+        #[ink(message)]
+        pub fn has_role(&self, role: RoleId, account: AccountId) -> bool {
+            *self.roles.get(&(role, account)).unwrap_or(&false)
+        }
+
+        /// Returns the role whose members can grant and revoke `role`.
+        #[ink(message)]
+        pub fn get_role_admin(&self, role: RoleId) -> RoleId {
+            *self.role_admins.get(&role).unwrap_or(&ADMIN_ROLE)
+        }
+
+        /// Grants `role` to `account`. The caller needs the admin role of `role`.
+        #[ink(message)]
+        pub fn grant_role(
+            &mut self,
+            role: RoleId,
+            account: AccountId,
+        ) -> Result<(), Error> {
+            self.ensure_migrated()?;
+            let caller = self.env().caller();
+            self.ensure_role(self.get_role_admin(role), caller)?;
+            self.ensure_no_council()?;
+            self.grant_role_to(role, account, caller);
+            Ok(())
+        }
+
+        /// Revokes `role` from `account`. The caller needs the admin role of `role`.
+        #[ink(message)]
+        pub fn revoke_role(
+            &mut self,
+            role: RoleId,
+            account: AccountId,
+        ) -> Result<(), Error> {
+            self.ensure_migrated()?;
+            let caller = self.env().caller();
+            self.ensure_role(self.get_role_admin(role), caller)?;
+            self.ensure_no_council()?;
+            self.revoke_role_from(role, account, caller);
+            Ok(())
+        }
+
+        /// Gives up `role` held by the caller.
+        #[ink(message)]
+        pub fn renounce_role(&mut self, role: RoleId) -> Result<(), Error> {
+            self.ensure_migrated()?;
+            let caller = self.env().caller();
+            self.ensure_role(role, caller)?;
+            self.revoke_role_from(role, caller, caller);
+            Ok(())
+        }
+
+        /// Makes `admin_role` the role whose members can grant and revoke `role`.
+        #[ink(message)]
+        pub fn set_role_admin(
+            &mut self,
+            role: RoleId,
+            admin_role: RoleId,
+        ) -> Result<(), Error> {
+            self.ensure_migrated()?;
+            let caller = self.env().caller();
+            self.ensure_role(ADMIN_ROLE, caller)?;
+            self.ensure_no_council()?;
+            self.change_role_admin(role, admin_role);
+            Ok(())
+        }
+
+        /// Returns the version of the layout the stored state is in.
+        #[ink(message)]
+        pub fn storage_version(&self) -> u32 {
+            ink_env::get_contract_storage::<u32>(&Key::from(STORAGE_VERSION_KEY))
+                .ok()
+                .flatten()
+                // deployments of storage version 1 kept it in the storage struct
+                .unwrap_or(1)
+        }
+
+        /// Replaces the code of the contract with the code uploaded under `code_hash`.
+        /// If the new code bumps the storage version, the contract refuses to operate
+        /// until `migrate` is called.
+        #[ink(message)]
+        pub fn upgrade(&mut self, code_hash: [u8; 32]) -> Result<(), Error> {
+            let caller = self.env().caller();
+            self.ensure_role(ADMIN_ROLE, caller)?;
+            // the council is part of storage version 2, and state that is not
+            // migrated yet has none, so a bad upgrade can still be rolled back
+            if self.storage_version() >= 2 {
+                self.ensure_no_council()?;
+            }
+            self.set_code(code_hash)
+        }
+
+        /// Transforms the stored state to the layout of the current code, one
+        /// version at a time.
+        #[ink(message)]
+        pub fn migrate(&mut self) -> Result<(), Error> {
+            let caller = self.env().caller();
+            self.ensure_role(ADMIN_ROLE, caller)?;
+            let from_version = self.storage_version();
+            if from_version >= STORAGE_VERSION {
+                return Err(Error::NotAllowed);
+            }
+            // Every layout change adds a step here transforming state from the
+            // previous version.
+            for version in from_version..STORAGE_VERSION {
+                if version == 1 {
+                    self.migrate_from_v1();
+                }
+            }
+            write_storage_version(STORAGE_VERSION);
+            self.env().emit_event(Migrated {
+                from_version,
+                to_version: STORAGE_VERSION,
+            });
+            Ok(())
+        }
+
+        /// Returns true if minting, transfers and swaps are stopped.
+        #[ink(message)]
+        pub fn paused(&self) -> bool {
+            self.paused
+        }
+
+        /// Stops minting, transfers and swaps. Swap proposals can still be rejected.
+        /// Pausers keep this right while a council is set up, so incidents can be
+        /// stopped without waiting for approvals.
+        #[ink(message)]
+        pub fn pause(&mut self) -> Result<(), Error> {
+            self.ensure_migrated()?;
+            let caller = self.env().caller();
+            self.ensure_role(PAUSER_ROLE, caller)?;
+            self.set_paused(true, caller)
+        }
+
+        /// Resumes minting, transfers and swaps.
+        #[ink(message)]
+        pub fn unpause(&mut self) -> Result<(), Error> {
+            self.ensure_migrated()?;
+            let caller = self.env().caller();
+            self.ensure_role(PAUSER_ROLE, caller)?;
+            self.ensure_no_council()?;
+            self.set_paused(false, caller)
+        }
+
+        /// Returns the number of council members.
+        #[ink(message)]
+        pub fn council_count(&self) -> u32 {
+            *self.council_count
+        }
+
+        /// Returns the council member at `index`.
+        #[ink(message)]
+        pub fn council_member_by_index(&self, index: u32) -> Option<AccountId> {
+            self.council.get(&index).cloned()
+        }
+
+        /// Returns true if `account` is a member of the council.
+        #[ink(message)]
+        pub fn is_council_member(&self, account: AccountId) -> bool {
+            self.council_index.contains_key(&account)
+        }
+
+        /// Returns the approvals needed to execute a proposal, 0 if there is no council.
+        #[ink(message)]
+        pub fn council_threshold(&self) -> u32 {
+            *self.council_threshold
+        }
+
+        /// Returns the number of proposals ever made. Proposal ids run from 0 up to it.
+        #[ink(message)]
+        pub fn proposal_count(&self) -> u32 {
+            *self.proposals_count
+        }
+
+        /// Returns the proposal with id `id`.
+        #[ink(message)]
+        pub fn get_proposal(&self, id: u32) -> Option<Proposal> {
+            self.proposals.get(&id).cloned()
+        }
+
+        /// Returns the number of current council members approving proposal `id`.
+        #[ink(message)]
+        pub fn proposal_approvals(&self, id: u32) -> u32 {
+            (0..*self.council_count)
+                .filter_map(|index| self.council.get(&index))
+                .filter(|member| self.has_approved(id, **member))
+                .count() as u32
+        }
+
+        /// Returns true if `account` approved proposal `id`.
+        #[ink(message)]
+        pub fn has_approved(&self, id: u32, account: AccountId) -> bool {
+            self.proposal_approvals.contains_key(&(id, account))
+        }
+
+        /// Sets up a council that has to approve admin actions from then on. Once the
+        /// council exists it can only be changed through one of its proposals.
+        #[ink(message)]
+        pub fn set_council(
+            &mut self,
+            members: Vec<AccountId>,
+            threshold: u32,
+        ) -> Result<(), Error> {
+            self.ensure_migrated()?;
+            let caller = self.env().caller();
+            self.ensure_role(ADMIN_ROLE, caller)?;
+            self.ensure_no_council()?;
+            self.replace_council(members, threshold)
+        }
+
+        /// Proposes `action` to the council. The proposal counts as approved by the
+        /// caller and is executed right away if that meets the threshold.
+        #[ink(message)]
+        pub fn propose(&mut self, action: AdminAction) -> Result<(), Error> {
+            self.ensure_migrated()?;
+            let caller = self.env().caller();
+            if !self.is_council_member(caller) {
+                return Err(Error::NotCouncilMember);
+            }
+            let id = *self.proposals_count;
+            let expires_at = self
+                .env()
+                .block_timestamp()
+                .saturating_add(PROPOSAL_LIFETIME);
+            let ready = *self.council_threshold <= 1;
+            if ready {
+                self.execute_action(action.clone(), caller)?;
+            }
+            self.proposals.insert(
+                id,
+                Proposal {
+                    action,
+                    proposer: caller,
+                    expires_at,
+                    executed: false,
+                },
+            );
+            *self.proposals_count += 1;
+            self.proposal_approvals.insert((id, caller), true);
+            self.env().emit_event(ProposalCreated {
+                id,
+                proposer: caller,
+                expires_at,
+            });
+            self.env().emit_event(ProposalApproved {
+                id,
+                approver: caller,
+            });
+            if ready {
+                self.mark_executed(id);
+            }
+            Ok(())
+        }
+
+        /// Approves proposal `id`, executing it once the threshold is met.
+        #[ink(message)]
+        pub fn approve_proposal(&mut self, id: u32) -> Result<(), Error> {
+            self.ensure_migrated()?;
+            let caller = self.env().caller();
+            if !self.is_council_member(caller) {
+                return Err(Error::NotCouncilMember);
+            }
+            let proposal = self.open_proposal(id)?;
+            if self.has_approved(id, caller) {
+                return Err(Error::NotAllowed);
+            }
+            let ready = self.proposal_approvals(id) + 1 >= *self.council_threshold;
+            if ready {
+                self.execute_action(proposal.action, caller)?;
+            }
+            self.proposal_approvals.insert((id, caller), true);
+            self.env().emit_event(ProposalApproved {
+                id,
+                approver: caller,
+            });
+            if ready {
+                self.mark_executed(id);
+            }
+            Ok(())
+        }
+
+        /// Executes proposal `id` once it has enough approvals, for proposals that
+        /// reached the threshold after it was lowered or whose execution failed before.
+        #[ink(message)]
+        pub fn execute_proposal(&mut self, id: u32) -> Result<(), Error> {
+            self.ensure_migrated()?;
+            let caller = self.env().caller();
+            if !self.is_council_member(caller) {
+                return Err(Error::NotCouncilMember);
+            }
+            let proposal = self.open_proposal(id)?;
+            if self.proposal_approvals(id) < *self.council_threshold {
+                return Err(Error::NotAllowed);
+            }
+            self.execute_action(proposal.action, caller)?;
+            self.mark_executed(id);
+            Ok(())
+        }
+
+        /// Returns the owner of the contract, `None` once ownership is renounced.
+        #[ink(message)]
+        pub fn owner(&self) -> Option<AccountId> {
+            if self.contract_owner == AccountId::from([0x0; 32]) {
+                None
+            } else {
+                Some(self.contract_owner)
+            }
+        }
+
+        /// Returns the account that can accept ownership of the contract.
+        #[ink(message)]
+        pub fn pending_owner(&self) -> Option<AccountId> {
+            self.pending_owner
+        }
+
+        /// Proposes `new_owner` as owner of the contract. Ownership only changes once
+        /// `new_owner` accepts it.
+        #[ink(message)]
+        pub fn transfer_ownership(&mut self, new_owner: AccountId) -> Result<(), Error> {
+            self.ensure_migrated()?;
+            let caller = self.env().caller();
+            if caller != self.contract_owner {
+                return Err(Error::NotContractOwner);
+            }
+            self.ensure_no_council()?;
+            self.propose_owner(new_owner)
+        }
+
+        /// Accepts ownership of the contract proposed to the caller.
+        #[ink(message)]
+        pub fn accept_ownership(&mut self) -> Result<(), Error> {
+            self.ensure_migrated()?;
+            let caller = self.env().caller();
+            if self.pending_owner != Some(caller) {
+                return Err(Error::NotAllowed);
+            }
+            let previous_owner = self.owner();
+            for role in OWNER_ROLES.iter() {
+                if let Some(previous_owner) = previous_owner {
+                    self.revoke_role_from(*role, previous_owner, caller);
+                }
+                self.grant_role_to(*role, caller, caller);
+            }
+            self.contract_owner = caller;
+            self.pending_owner = None;
+            self.env().emit_event(OwnershipTransferred {
+                previous_owner,
+                new_owner: Some(caller),
+            });
+            Ok(())
+        }
+
+        /// Gives up ownership of the contract for good, together with every role the
+        /// owner holds. Issuer management and fees are frozen afterwards.
+        #[ink(message)]
+        pub fn renounce_ownership(&mut self) -> Result<(), Error> {
+            self.ensure_migrated()?;
+            let caller = self.env().caller();
+            if caller != self.contract_owner {
+                return Err(Error::NotContractOwner);
+            }
+            self.ensure_no_council()?;
+            for role in OWNER_ROLES.iter() {
+                self.revoke_role_from(*role, caller, caller);
+            }
+            self.contract_owner = AccountId::from([0x0; 32]);
+            self.pending_owner = None;
+            self.env().emit_event(OwnershipTransferred {
+                previous_owner: Some(caller),
+                new_owner: None,
+            });
+            Ok(())
+        }
+
+        /// Approve issuer to mint contract.
+        #[ink(message)]
+        pub fn set_issuer(
+            &mut self,
+            to: AccountId,
+            name: String,
+            status: bool,
+        ) -> Result<(), Error> {
+            self.ensure_migrated()?;
+            let caller = self.env().caller();
+            if self.has_role(ISSUER_MANAGER_ROLE, caller) {
+                self.ensure_no_council()?;
+                self.write_issuer(to, name, status);
+                Ok(())
+            } else {
+                return Err(Error::MissingRole);
+            }
+        }
+
+        /// Removes `to` from the registered issuers. Its sets can no longer be minted into.
+        #[ink(message)]
+        pub fn remove_issuer(&mut self, to: AccountId) -> Result<(), Error> {
+            self.ensure_migrated()?;
+            let caller = self.env().caller();
+            self.ensure_role(ISSUER_MANAGER_ROLE, caller)?;
+            self.ensure_no_council()?;
+            self.delete_issuer(to)
+        }
+
+        /// Sets the protocol fee taken from mints and sales.
+        #[ink(message)]
+        pub fn set_fee(&mut self, basis_points: u16) -> Result<(), Error> {
+            self.ensure_migrated()?;
+            let caller = self.env().caller();
+            self.ensure_role(FEE_MANAGER_ROLE, caller)?;
+            self.ensure_no_council()?;
+            self.update_fee(basis_points)
+        }
+
+        /// Withdraws `amount` of the accrued protocol fees to `to`.
+        #[ink(message)]
+        pub fn withdraw_fees(
+            &mut self,
+            to: AccountId,
+            amount: Balance,
+        ) -> Result<(), Error> {
+            self.ensure_migrated()?;
+            let caller = self.env().caller();
+            self.ensure_role(FEE_MANAGER_ROLE, caller)?;
+            self.ensure_no_council()?;
+            self.send_fees(None, to, amount)
+        }
+
+        /// Withdraws `amount` of the protocol fees accrued in the PSP22 contract
+        /// `currency` to `to`.
+        #[ink(message)]
+        pub fn withdraw_token_fees(
+            &mut self,
+            currency: AccountId,
+            to: AccountId,
+            amount: Balance,
+        ) -> Result<(), Error> {
+            self.ensure_migrated()?;
+            let caller = self.env().caller();
+            self.ensure_role(FEE_MANAGER_ROLE, caller)?;
+            self.ensure_no_council()?;
+            self.send_fees(Some(currency), to, amount)
+        }
+
+        /// Sets the PSP22 contract tokens of the set are paid in. `None` switches
+        /// the set back to the native currency.
+        #[ink(message)]
+        pub fn set_payment_currency(
+            &mut self,
+            setid: u32,
+            currency: Option<AccountId>,
+        ) -> Result<(), Error> {
+            self.ensure_migrated()?;
+            let caller = self.env().caller();
+            let owner = self.nft_set.get(&setid).map(|v| v.owner.clone());
+            if owner != Some(caller) {
+                return Err(Error::NotIssuer);
+            }
+            match currency {
+                Some(currency) => self.set_currency.insert(setid, currency),
+                None => self.set_currency.take(&setid),
+            };
+            Ok(())
+        }
+
+        #[ink(message)]
+        pub fn create_nft_set(
+            &mut self,
+            id: u32,
+            name: String,
+            description: String,
+            status: bool,
+        ) -> Result<(), Error> {
+            self.ensure_migrated()?;
+            let caller = self.env().caller();
+            let stat = self.nft_issuer.get(&caller).map(|v| v.status.clone());
+            if stat == Some(true) {
+                // set ids can never be claimed again, whatever the status of the set
+                if !self.nft_set.contains_key(&id) {
+                    self.nft_set.insert(
+                        id,
+                        NFTSetFeatures {
+                            name,
+                            description,
+                            status,
+                            owner: caller,
+                            revision: 0,
+                            metadata_frozen: false,
+                            max_supply: None,
+                            sealed: false,
+                        },
+                    );
+                    self.add_set_to_enumeration(caller, id);
+                    Ok(())
+                } else {
+                    return Err(Error::SetExists);
+                }
+            } else {
+                return Err(Error::NotIssuer);
+            }
+        }
+
+
+        /// Sets the royalty paid on secondary sales of every token in the set.
+        #[ink(message)]
+        pub fn set_royalty(
+            &mut self,
+            setid: u32,
+            receiver: AccountId,
+            basis_points: u16,
+        ) -> Result<(), Error> {
+            self.ensure_migrated()?;
+            let caller = self.env().caller();
+            let owner = self.nft_set.get(&setid).map(|v| v.owner.clone());
+            if owner != Some(caller) {
+                return Err(Error::NotIssuer);
+            }
+            if basis_points > MAX_BASIS_POINTS {
+                return Err(Error::InvalidBasisPoints);
+            }
+            self.set_royalty.insert(
+                setid,
+                RoyaltyInfo {
+                    receiver,
+                    basis_points,
+                },
+            );
+            Ok(())
+        }
+
+        /// Sets a royalty for a single token, overriding the royalty of its set.
+        /// Only the issuer of the token can sign this transaction.
+        #[ink(message)]
+        pub fn set_token_royalty(
+            &mut self,
+            id: TokenId,
+            receiver: AccountId,
+            basis_points: u16,
+        ) -> Result<(), Error> {
+            self.ensure_migrated()?;
+            let caller = self.env().caller();
+            let issuer = self.token_features.get(&id).map(|v| v.issuer.clone());
+            if issuer != Some(caller) {
+                return Err(Error::NotIssuer);
+            }
+            if basis_points > MAX_BASIS_POINTS {
+                return Err(Error::InvalidBasisPoints);
+            }
+            self.token_royalty.insert(
+                id,
+                RoyaltyInfo {
+                    receiver,
+                    basis_points,
+                },
+            );
+            Ok(())
+        }
+
+        /// Sets the metadata tokens minted into the set by a sale receive.
+        #[ink(message)]
+        pub fn set_token_template(
+            &mut self,
+            setid: u32,
+            template: TokenTemplate,
+        ) -> Result<(), Error> {
+            self.ensure_migrated()?;
+            let caller = self.env().caller();
+            let owner = self.nft_set.get(&setid).map(|v| v.owner.clone());
+            if owner != Some(caller) {
+                return Err(Error::NotIssuer);
+            }
+            validate_template(&template)?;
+            self.set_templates.insert(setid, template);
+            Ok(())
+        }
+
+        /// Opens a public sale of the set, or changes the sale already running.
+        #[ink(message)]
+        pub fn set_sale(
+            &mut self,
+            setid: u32,
+            price: Balance,
+            max_supply: u32,
+            wallet_limit: u32,
+            start: Timestamp,
+            end: Timestamp,
+        ) -> Result<(), Error> {
+            self.ensure_migrated()?;
+            let caller = self.env().caller();
+            let owner = self.nft_set.get(&setid).map(|v| v.owner.clone());
+            if owner != Some(caller) {
+                return Err(Error::NotIssuer);
+            }
+            if start >= end {
+                return Err(Error::InvalidSaleWindow);
+            }
+            let sold = self.set_sales.get(&setid).map(|v| v.sold).unwrap_or(0);
+            if max_supply < sold {
+                return Err(Error::SupplyExceeded);
+            }
+            self.set_sales.insert(
+                setid,
+                SaleConfig {
+                    price,
+                    max_supply,
+                    wallet_limit,
+                    start,
+                    end,
+                    sold,
+                },
+            );
+            Ok(())
+        }
+
+        /// Buys `quantity` tokens in the public sale of the set. The tokens get
+        /// sequential ids and the template metadata of the set.
+        #[ink(message, payable)]
+        pub fn purchase(&mut self, setid: u32, quantity: u32) -> Result<(), Error> {
+            self.ensure_migrated()?;
+            self.ensure_not_paused()?;
+            let caller = self.env().caller();
+            let sale = self.set_sales.get(&setid).cloned().ok_or(Error::NoSale)?;
+            let now = self.env().block_timestamp();
+            if now < sale.start || now >= sale.end {
+                return Err(Error::SaleNotActive);
+            }
+            let purchased = self.purchased(setid, caller);
+            let bought = self.bought_from_sale(setid, caller);
+            if bought.saturating_add(quantity) > sale.wallet_limit {
+                return Err(Error::WalletLimitReached);
+            }
+            self.sell_tokens(setid, caller, quantity)?;
+            self.sale_purchases
+                .insert((setid, caller), purchased + quantity);
+            Ok(())
+        }
+
+        /// Sets the Merkle root of the accounts allowed to buy from the sale of the
+        /// set before it opens. `None` removes the allowlist.
+        #[ink(message)]
+        pub fn set_allowlist(
+            &mut self,
+            setid: u32,
+            root: Option<Hash>,
+        ) -> Result<(), Error> {
+            self.ensure_migrated()?;
+            let caller = self.env().caller();
+            let owner = self.nft_set.get(&setid).map(|v| v.owner.clone());
+            if owner != Some(caller) {
+                return Err(Error::NotIssuer);
+            }
+            match root {
+                Some(root) => self.allowlist_roots.insert(setid, root),
+                None => self.allowlist_roots.take(&setid),
+            };
+            Ok(())
+        }
+
+        /// Buys `quantity` tokens from the sale of the set as an allowlisted account.
+        /// `proof` proves the blake2 hash of the caller is a leaf of the allowlist.
+        /// Allowlisted accounts can buy before the sale opens. Tokens bought this way
+        /// count towards the same wallet limit as purchases.
+        #[ink(message, payable)]
+        pub fn allowlist_mint(
+            &mut self,
+            setid: u32,
+            quantity: u32,
+            proof: Vec<Hash>,
+        ) -> Result<(), Error> {
+            self.ensure_migrated()?;
+            self.ensure_not_paused()?;
+            let caller = self.env().caller();
+            let root = *self.allowlist_roots.get(&setid).ok_or(Error::NotAllowed)?;
+            let leaf = self.env().hash_encoded::<Blake2x256, _>(&caller);
+            if !self.verify_merkle_proof(root, leaf, &proof) {
+                return Err(Error::NotAllowed);
+            }
+            let sale = self.set_sales.get(&setid).cloned().ok_or(Error::NoSale)?;
+            if self.env().block_timestamp() >= sale.end {
+                return Err(Error::SaleNotActive);
+            }
+            let claimed = self.allowlist_claimed(setid, caller);
+            let bought = self.bought_from_sale(setid, caller);
+            if bought.saturating_add(quantity) > sale.wallet_limit {
+                return Err(Error::WalletLimitReached);
+            }
+            self.sell_tokens(setid, caller, quantity)?;
+            self.allowlist_claimed
+                .insert((setid, caller), claimed + quantity);
+            Ok(())
+        }
+
+        /// Mints every `(id, metadata, to)` entry of `tokens` into the set. Nothing is
+        /// minted if any id already exists or appears twice.
+        #[ink(message)]
+        pub fn mint_batch(
+            &mut self,
+            setid: u32,
+            tokens: Vec<(TokenId, TokenTemplate, AccountId)>,
+        ) -> Result<(), Error> {
+            self.ensure_migrated()?;
+            self.ensure_not_paused()?;
+            let caller = self.env().caller();
+            if !self.can_mint_into(setid, caller) {
+                return Err(Error::NotIssuer);
+            }
+            if tokens.len() > MAX_BATCH_LEN as usize {
+                return Err(Error::BatchTooLarge);
+            }
+            let owner = self.mintable_set_owner(setid, tokens.len() as u32)?;
+            let mut ids = BTreeSet::new();
+            for (id, metadata, to) in tokens.iter() {
+                if self.is_taken(*id) || !ids.insert(*id) {
+                    return Err(Error::TokenExists);
+                }
+                validate_template(metadata)?;
+                if *to == AccountId::from([0x0; 32]) {
+                    return Err(Error::NotAllowed);
+                }
+            }
+            for (id, metadata, to) in tokens.into_iter() {
+                self.mint_token(setid, id, metadata, owner, to)?;
+            }
+            Ok(())
+        }
+
+        /// Mints a token with the template metadata of the set to every account of
+        /// `recipients`, using sequential ids.
+        #[ink(message)]
+        pub fn airdrop(
+            &mut self,
+            setid: u32,
+            recipients: Vec<AccountId>,
+        ) -> Result<(), Error> {
+            self.ensure_migrated()?;
+            self.ensure_not_paused()?;
+            let caller = self.env().caller();
+            if !self.can_mint_into(setid, caller) {
+                return Err(Error::NotIssuer);
+            }
+            if recipients.len() > MAX_BATCH_LEN as usize {
+                return Err(Error::BatchTooLarge);
+            }
+            let owner = self.mintable_set_owner(setid, recipients.len() as u32)?;
+            let template = self.token_template(setid).ok_or(Error::NoTemplate)?;
+            if recipients.contains(&AccountId::from([0x0; 32])) {
+                return Err(Error::NotAllowed);
+            }
+            for to in recipients.into_iter() {
+                let id = self.next_free_token_id();
+                self.mint_token(setid, id, template.clone(), owner, to)?;
+            }
+            Ok(())
+        }
+
+        /// Mints the token of a voucher signed by the owner of its set. `signature`
+        /// is the ECDSA signature of the blake2 hash of the SCALE encoded pair of this
+        /// contract's account id and the voucher. The price of the voucher is paid to
+        /// the issuer.
+        #[ink(message, payable)]
+        pub fn redeem_voucher(
+            &mut self,
+            voucher: Voucher,
+            signature: [u8; 65],
+        ) -> Result<(), Error> {
+            self.ensure_migrated()?;
+            self.ensure_not_paused()?;
+            let caller = self.env().caller();
+            // the contract is signed too, so a voucher can't be replayed on another
+            // deployment
+            let hash = self
+                .env()
+                .hash_encoded::<Blake2x256, _>(&(self.env().account_id(), &voucher));
+            if self.redeemed_vouchers.contains_key(&Hash::from(hash)) {
+                return Err(Error::VoucherRedeemed);
+            }
+            if self.env().block_timestamp() >= voucher.expires_at {
+                return Err(Error::VoucherExpired);
+            }
+            if voucher.recipient.is_some() && voucher.recipient != Some(caller) {
+                return Err(Error::NotAllowed);
+            }
+            let public_key = self
+                .env()
+                .ecdsa_recover(&signature, &hash)
+                .map_err(|_| Error::InvalidSignature)?;
+            let signer =
+                AccountId::from(self.env().hash_bytes::<Blake2x256>(&public_key));
+            if self.mintable_set_owner(voucher.setid, 1)? != signer {
+                return Err(Error::InvalidSignature);
+            }
+            if self.is_taken(voucher.id) {
+                return Err(Error::TokenExists);
+            }
+            validate_template(&voucher.metadata)?;
+            let currency = self.payment_currency(voucher.setid);
+            self.collect_payment(currency, caller, voucher.price)?;
+            self.redeemed_vouchers.insert(Hash::from(hash), caller);
+            self.mint_token(voucher.setid, voucher.id, voucher.metadata, signer, caller)?;
+            self.env().emit_event(VoucherRedeemed {
+                voucher: Hash::from(hash),
+                redeemer: caller,
+                id: voucher.id,
+            });
+            self.pay_with_fee(currency, signer, voucher.price)
+        }
+
+        /// Allows `minter` to mint into the set. Tokens it mints are issued by the owner
+        /// of the set.
+        #[ink(message)]
+        pub fn grant_minter(
+            &mut self,
+            setid: u32,
+            minter: AccountId,
+        ) -> Result<(), Error> {
+            self.ensure_migrated()?;
+            let caller = self.env().caller();
+            let owner = self.nft_set.get(&setid).map(|v| v.owner.clone());
+            if owner != Some(caller) {
+                return Err(Error::NotIssuer);
+            }
+            if self.is_set_minter(setid, minter) {
+                return Ok(());
+            }
+            let length = self.set_minter_balance(setid);
+            self.set_minters.insert((setid, length), minter);
+            self.set_minters_index.insert((setid, minter), length);
+            self.set_minters_count.insert(setid, length + 1);
+            self.env().emit_event(MinterGranted { setid, minter });
+            Ok(())
+        }
+
+        /// Takes away the right of `minter` to mint into the set.
+        #[ink(message)]
+        pub fn revoke_minter(
+            &mut self,
+            setid: u32,
+            minter: AccountId,
+        ) -> Result<(), Error> {
+            self.ensure_migrated()?;
+            let caller = self.env().caller();
+            let owner = self.nft_set.get(&setid).map(|v| v.owner.clone());
+            if owner != Some(caller) {
+                return Err(Error::NotIssuer);
+            }
+            let index = self
+                .set_minters_index
+                .take(&(setid, minter))
+                .ok_or(Error::NotAllowed)?;
+            let last_index = self.set_minter_balance(setid) - 1;
+            // When the minter to delete is the last minter, the swap operation is unnecessary
+            if index != last_index {
+                let last_minter = *self
+                    .set_minters
+                    .get(&(setid, last_index))
+                    .ok_or(Error::CannotFetchValue)?;
+                self.set_minters.insert((setid, index), last_minter);
+                self.set_minters_index.insert((setid, last_minter), index);
+            }
+            self.set_minters.take(&(setid, last_index));
+            self.set_minters_count.insert(setid, last_index);
+            self.env().emit_event(MinterRevoked { setid, minter });
+            Ok(())
+        }
 
-        /// Approve issuer to mint contract.
+        /// Allows accounts with the minter role to mint into the set on behalf of its
+        /// owner, or stops them from doing so.
         #[ink(message)]
-        pub fn set_issuer(
+        pub fn set_role_minting(
             &mut self,
-            to: AccountId,
-            name: String,
-            status: bool,
+            setid: u32,
+            allowed: bool,
         ) -> Result<(), Error> {
+            self.ensure_migrated()?;
             let caller = self.env().caller();
-            if caller == self.contract_owner {
-                self.nft_issuer.insert(to, IssuerFeatures { name, status });
-                Ok(())
+            let owner = self.nft_set.get(&setid).map(|v| v.owner.clone());
+            if owner != Some(caller) {
+                return Err(Error::NotIssuer);
+            }
+            if allowed {
+                self.role_minting_sets.insert(setid, true);
             } else {
-                return Err(Error::NotContractOwner);
+                self.role_minting_sets.take(&setid);
             }
+            self.env().emit_event(RoleMintingChanged { setid, allowed });
+            Ok(())
         }
 
+        /// Registers `merchant` as accepting the discounts of tokens issued by the caller.
         #[ink(message)]
-        pub fn create_nft_set(
+        pub fn add_merchant(&mut self, merchant: AccountId) -> Result<(), Error> {
+            self.ensure_migrated()?;
+            let caller = self.env().caller();
+            if self.is_issuer(caller) != Some(true) {
+                return Err(Error::NotIssuer);
+            }
+            if self.is_merchant(caller, merchant) {
+                return Ok(());
+            }
+            let length = self.merchant_balance(caller);
+            self.issuer_merchants.insert((caller, length), merchant);
+            self.issuer_merchants_index
+                .insert((caller, merchant), length);
+            self.issuer_merchants_count.insert(caller, length + 1);
+            self.env().emit_event(MerchantAdded {
+                issuer: caller,
+                merchant,
+            });
+            Ok(())
+        }
+
+        /// Removes `merchant` from the merchants accepting the discounts of the caller.
+        #[ink(message)]
+        pub fn remove_merchant(&mut self, merchant: AccountId) -> Result<(), Error> {
+            self.ensure_migrated()?;
+            let caller = self.env().caller();
+            let index = self
+                .issuer_merchants_index
+                .take(&(caller, merchant))
+                .ok_or(Error::NotMerchant)?;
+            let last_index = self.merchant_balance(caller) - 1;
+            // When the merchant to delete is the last merchant, the swap operation is unnecessary
+            if index != last_index {
+                let last_merchant = *self
+                    .issuer_merchants
+                    .get(&(caller, last_index))
+                    .ok_or(Error::CannotFetchValue)?;
+                self.issuer_merchants.insert((caller, index), last_merchant);
+                self.issuer_merchants_index
+                    .insert((caller, last_merchant), index);
+            }
+            self.issuer_merchants.take(&(caller, last_index));
+            self.issuer_merchants_count.insert(caller, last_index);
+            self.env().emit_event(MerchantRemoved {
+                issuer: caller,
+                merchant,
+            });
+            Ok(())
+        }
+
+        /// Uses the discount of token `id` once. Either a merchant of the issuer of the
+        /// token signs this, or the holder does with `cosignature`, the signature of a
+        /// merchant over the encoded `(contract, id, nonce)`, where `nonce` is the
+        /// current `discount_nonce` of the token.
+        #[ink(message)]
+        pub fn redeem_discount(
             &mut self,
-            id: u32,
-            name: String,
-            description: String,
-            status: bool,
+            id: TokenId,
+            cosignature: Option<[u8; 65]>,
         ) -> Result<(), Error> {
+            self.ensure_migrated()?;
             let caller = self.env().caller();
-            let stat = self.nft_issuer.get(&caller).map(|v| v.status.clone());
-            if stat == Some(true) {
-                let exists = self.nft_set.get(&id).map(|v| v.status.clone());
-                if exists != Some(true) {
-                    self.nft_set.insert(
-                        id,
-                        NFTSetFeatures {
-                            name,
-                            description,
-                            status,
-                            owner: caller,
-                        },
-                    );
-                    self.add_set_to_enumeration(caller, id);
-                    Ok(())
-                } else {
-                    return Err(Error::NotIssuer);
+            let holder = self.owner_of(id).ok_or(Error::TokenNotFound)?;
+            let issuer = self
+                .token_features
+                .get(&id)
+                .map(|v| v.issuer)
+                .ok_or(Error::TokenNotFound)?;
+            let mut terms = self.discount_of(id).ok_or(Error::NoDiscount)?;
+            let nonce = self.discount_nonce(id);
+            let merchant = match cosignature {
+                None => caller,
+                Some(signature) => {
+                    if caller != holder {
+                        return Err(Error::NotOwner);
+                    }
+                    let message = (self.env().account_id(), id, nonce);
+                    let hash = self.env().hash_encoded::<Blake2x256, _>(&message);
+                    let public_key = self
+                        .env()
+                        .ecdsa_recover(&signature, &hash)
+                        .map_err(|_| Error::InvalidSignature)?;
+                    AccountId::from(self.env().hash_bytes::<Blake2x256>(&public_key))
+                }
+            };
+            if !self.is_merchant(issuer, merchant) {
+                return Err(Error::NotMerchant);
+            }
+            if !self.is_discount_in_window(id, &terms) {
+                return Err(Error::DiscountInactive);
+            }
+            if let Some(remaining_uses) = terms.remaining_uses {
+                if remaining_uses == 0 {
+                    return Err(Error::DiscountUsedUp);
+                }
+                terms.remaining_uses = Some(remaining_uses - 1);
+            }
+            let remaining_uses = terms.remaining_uses;
+            self.token_discounts.insert(id, terms);
+            // every redemption needs a fresh co-signature
+            self.discount_redemptions.insert(id, nonce + 1);
+            self.env().emit_event(DiscountRedeemed {
+                id,
+                merchant,
+                holder,
+                remaining_uses,
+            });
+            Ok(())
+        }
+
+        /// Moves the end of the discount of token `id` to `valid_until`, `None` for
+        /// never. Discounts can only be extended this way, never shortened.
+        #[ink(message)]
+        pub fn extend_discount(
+            &mut self,
+            id: TokenId,
+            valid_until: Option<Timestamp>,
+        ) -> Result<(), Error> {
+            self.ensure_migrated()?;
+            let caller = self.env().caller();
+            let issuer = self.token_features.get(&id).map(|v| v.issuer);
+            if issuer != Some(caller) {
+                return Err(Error::NotIssuer);
+            }
+            let mut terms = self.discount_of(id).ok_or(Error::NoDiscount)?;
+            let extended = match (terms.valid_until, valid_until) {
+                (None, _) => false,
+                (Some(_), None) => true,
+                (Some(old), Some(new)) => new > old,
+            };
+            if !extended {
+                return Err(Error::InvalidDiscount);
+            }
+            terms.valid_until = valid_until;
+            self.token_discounts.insert(id, terms);
+            self.env().emit_event(DiscountExtended { id, valid_until });
+            Ok(())
+        }
+
+        /// Takes the discount of token `id` away for good.
+        #[ink(message)]
+        pub fn revoke_discount(&mut self, id: TokenId) -> Result<(), Error> {
+            self.ensure_migrated()?;
+            let caller = self.env().caller();
+            let issuer = self.token_features.get(&id).map(|v| v.issuer);
+            if issuer != Some(caller) {
+                return Err(Error::NotIssuer);
+            }
+            if self.discount_of(id).is_none() {
+                return Err(Error::NoDiscount);
+            }
+            self.token_discounts.take(&id);
+            if let Some(features) = self.token_features.get_mut(&id) {
+                features.has_discount = false;
+            }
+            self.env().emit_event(DiscountRevoked { id });
+            Ok(())
+        }
+
+        /// Limits the discounts of every token in the set to the window from
+        /// `valid_from` until `valid_until`. Passing `None` for both removes the limit.
+        #[ink(message)]
+        pub fn set_discount_window(
+            &mut self,
+            setid: u32,
+            valid_from: Option<Timestamp>,
+            valid_until: Option<Timestamp>,
+        ) -> Result<(), Error> {
+            self.ensure_migrated()?;
+            let caller = self.env().caller();
+            let owner = self.nft_set.get(&setid).map(|v| v.owner.clone());
+            if owner != Some(caller) {
+                return Err(Error::NotIssuer);
+            }
+            match (valid_from, valid_until) {
+                (Some(from), Some(until)) if from >= until => {
+                    return Err(Error::InvalidDiscount)
                 }
+                (None, None) => self.set_discount_windows.take(&setid),
+                _ => self
+                    .set_discount_windows
+                    .insert(setid, (valid_from, valid_until)),
+            };
+            self.env().emit_event(SetDiscountWindowChanged {
+                setid,
+                valid_from,
+                valid_until,
+            });
+            Ok(())
+        }
+
+        /// Sets the attribute `key` of token `id` to `value`.
+        #[ink(message)]
+        pub fn set_attribute(
+            &mut self,
+            id: TokenId,
+            key: String,
+            value: String,
+        ) -> Result<(), Error> {
+            self.ensure_migrated()?;
+            let caller = self.env().caller();
+            self.ensure_attributes_editable(id, caller)?;
+            validate_attribute(&key, &value)?;
+            if !self.token_attributes.contains_key(&(id, key.clone()))
+                && *self.token_attribute_count.get(&id).unwrap_or(&0) >= MAX_ATTRIBUTES
+            {
+                return Err(Error::TooManyAttributes);
+            }
+            self.write_attribute(id, key, value);
+            Ok(())
+        }
+
+        /// Removes the attribute `key` of token `id`.
+        #[ink(message)]
+        pub fn remove_attribute(
+            &mut self,
+            id: TokenId,
+            key: String,
+        ) -> Result<(), Error> {
+            self.ensure_migrated()?;
+            let caller = self.env().caller();
+            self.ensure_attributes_editable(id, caller)?;
+            let index = self
+                .token_attribute_keys_index
+                .take(&(id, key.clone()))
+                .ok_or(Error::InvalidAttribute)?;
+            let last_index = *self.token_attribute_count.get(&id).unwrap_or(&0) - 1;
+            // When the key to delete is the last key, the swap operation is unnecessary
+            if index != last_index {
+                let last_key = self
+                    .token_attribute_keys
+                    .get(&(id, last_index))
+                    .cloned()
+                    .ok_or(Error::CannotFetchValue)?;
+                self.token_attribute_keys
+                    .insert((id, index), last_key.clone());
+                self.token_attribute_keys_index
+                    .insert((id, last_key), index);
+            }
+            self.token_attribute_keys.take(&(id, last_index));
+            self.token_attribute_count.insert(id, last_index);
+            self.token_attributes.take(&(id, key.clone()));
+            self.env().emit_event(AttributeRemoved { id, key });
+            Ok(())
+        }
+
+        /// Freezes the attributes of token `id` for good.
+        #[ink(message)]
+        pub fn freeze_attributes(&mut self, id: TokenId) -> Result<(), Error> {
+            self.ensure_migrated()?;
+            let caller = self.env().caller();
+            self.ensure_attributes_editable(id, caller)?;
+            self.frozen_attributes.insert(id, true);
+            self.env().emit_event(AttributesFrozen { id });
+            Ok(())
+        }
+
+        /// Makes the tokens of the set non-transferable, or transferable again.
+        #[ink(message)]
+        pub fn set_soulbound(
+            &mut self,
+            setid: u32,
+            soulbound: bool,
+        ) -> Result<(), Error> {
+            self.ensure_migrated()?;
+            let caller = self.env().caller();
+            let owner = self.nft_set.get(&setid).map(|v| v.owner.clone());
+            if owner != Some(caller) {
+                return Err(Error::NotIssuer);
+            }
+            if soulbound {
+                self.soulbound_sets.insert(setid, true);
+            } else {
+                self.soulbound_sets.take(&setid);
+            }
+            self.env()
+                .emit_event(SetSoulboundChanged { setid, soulbound });
+            Ok(())
+        }
+
+        /// Makes token `id` non-transferable, or transferable again unless its set
+        /// is soulbound.
+        #[ink(message)]
+        pub fn set_token_soulbound(
+            &mut self,
+            id: TokenId,
+            soulbound: bool,
+        ) -> Result<(), Error> {
+            self.ensure_migrated()?;
+            let caller = self.env().caller();
+            let issuer = self.token_features.get(&id).map(|v| v.issuer);
+            if issuer != Some(caller) {
+                return Err(Error::NotIssuer);
+            }
+            if soulbound {
+                self.soulbound_tokens.insert(id, true);
             } else {
+                self.soulbound_tokens.take(&id);
+            }
+            self.env()
+                .emit_event(TokenSoulboundChanged { id, soulbound });
+            Ok(())
+        }
+
+        /// Burns the soulbound token `id`, e.g. a membership badge or certificate
+        /// that no longer applies to its holder.
+        #[ink(message)]
+        pub fn revoke_token(&mut self, id: TokenId) -> Result<(), Error> {
+            self.ensure_migrated()?;
+            let caller = self.env().caller();
+            let issuer = self.token_features.get(&id).map(|v| v.issuer);
+            if issuer != Some(caller) {
+                return Err(Error::NotIssuer);
+            }
+            if !self.is_soulbound(id) {
+                return Err(Error::NotAllowed);
+            }
+            let holder = self.owner_of(id).ok_or(Error::TokenNotFound)?;
+            self.burn_token(&holder, id)?;
+            self.env().emit_event(TokenRevoked {
+                id,
+                issuer: caller,
+                holder,
+            });
+            Ok(())
+        }
+
+        /// Transfers ownership of the set to `new_owner`, who must be an active issuer.
+        #[ink(message)]
+        pub fn transfer_set(
+            &mut self,
+            setid: u32,
+            new_owner: AccountId,
+        ) -> Result<(), Error> {
+            self.ensure_migrated()?;
+            let caller = self.env().caller();
+            let owner = self.nft_set.get(&setid).map(|v| v.owner.clone());
+            if owner != Some(caller) {
+                return Err(Error::NotIssuer);
+            }
+            if new_owner == caller {
+                return Err(Error::NotAllowed);
+            }
+            if self.is_issuer(new_owner) != Some(true) {
+                return Err(Error::NotIssuer);
+            }
+            self.remove_set_from_enumeration(&caller, setid)?;
+            self.add_set_to_enumeration(new_owner, setid)?;
+            if let Some(set) = self.nft_set.get_mut(&setid) {
+                set.owner = new_owner;
+            }
+            // minters were granted by the previous owner and mint on behalf of the owner
+            self.clear_set_minters(setid);
+            if self.role_minting_sets.take(&setid).is_some() {
+                self.env().emit_event(RoleMintingChanged {
+                    setid,
+                    allowed: false,
+                });
+            }
+            self.env().emit_event(SetTransferred {
+                from: caller,
+                to: new_owner,
+                setid,
+            });
+            Ok(())
+        }
+
+        /// Changes the name and description of the set, unless its metadata is frozen.
+        #[ink(message)]
+        pub fn update_nft_set(
+            &mut self,
+            setid: u32,
+            name: String,
+            description: String,
+        ) -> Result<(), Error> {
+            self.ensure_migrated()?;
+            let caller = self.env().caller();
+            let set = self.nft_set.get_mut(&setid).ok_or(Error::NotIssuer)?;
+            if set.owner != caller {
+                return Err(Error::NotIssuer);
+            }
+            if set.metadata_frozen {
+                return Err(Error::MetadataFrozen);
+            }
+            let old_name = core::mem::replace(&mut set.name, name.clone());
+            let old_description =
+                core::mem::replace(&mut set.description, description.clone());
+            set.revision += 1;
+            let revision = set.revision;
+            self.env().emit_event(SetMetadataUpdated {
+                setid,
+                revision,
+                old_name,
+                new_name: name,
+                old_description,
+                new_description: description,
+            });
+            Ok(())
+        }
+
+        /// Permanently locks the name and description of the set.
+        #[ink(message)]
+        pub fn freeze_set_metadata(&mut self, setid: u32) -> Result<(), Error> {
+            self.ensure_migrated()?;
+            let caller = self.env().caller();
+            let set = self.nft_set.get_mut(&setid).ok_or(Error::NotIssuer)?;
+            if set.owner != caller {
+                return Err(Error::NotIssuer);
+            }
+            if set.metadata_frozen {
+                return Err(Error::MetadataFrozen);
+            }
+            set.metadata_frozen = true;
+            self.env().emit_event(SetMetadataFrozen { setid });
+            Ok(())
+        }
+
+        /// Limits the number of tokens minted into the set. The limit can not be below
+        /// the number of tokens minted so far, and can not change once the set is
+        /// sealed.
+        #[ink(message)]
+        pub fn set_max_supply(
+            &mut self,
+            setid: u32,
+            max_supply: Option<u32>,
+        ) -> Result<(), Error> {
+            self.ensure_migrated()?;
+            let caller = self.env().caller();
+            let supply = self.nft_set_minted(setid);
+            let set = self.nft_set.get_mut(&setid).ok_or(Error::NotIssuer)?;
+            if set.owner != caller {
+                return Err(Error::NotIssuer);
+            }
+            if set.sealed {
+                return Err(Error::SetSealed);
+            }
+            if max_supply.map_or(false, |max| max < supply) {
+                return Err(Error::SupplyExceeded);
+            }
+            set.max_supply = max_supply;
+            self.env()
+                .emit_event(MaxSupplyChanged { setid, max_supply });
+            Ok(())
+        }
+
+        /// Permanently forbids minting into the set.
+        #[ink(message)]
+        pub fn seal_set(&mut self, setid: u32) -> Result<(), Error> {
+            self.ensure_migrated()?;
+            let caller = self.env().caller();
+            let set = self.nft_set.get_mut(&setid).ok_or(Error::NotIssuer)?;
+            if set.owner != caller {
                 return Err(Error::NotIssuer);
             }
+            if set.sealed {
+                return Err(Error::SetSealed);
+            }
+            set.sealed = true;
+            self.env().emit_event(SetSealed { setid });
+            Ok(())
         }
 
+        /// Activates or deactivates the set. Tokens can only be minted into active sets.
+        #[ink(message)]
+        pub fn set_nft_set_status(
+            &mut self,
+            setid: u32,
+            status: bool,
+        ) -> Result<(), Error> {
+            self.ensure_migrated()?;
+            let caller = self.env().caller();
+            let set = self.nft_set.get_mut(&setid).ok_or(Error::NotIssuer)?;
+            if set.owner != caller {
+                return Err(Error::NotIssuer);
+            }
+            if set.status != status {
+                set.status = status;
+                self.env().emit_event(SetStatusChanged { setid, status });
+            }
+            Ok(())
+        }
 
         #[ink(message, payable)]
         pub fn mint(
@@ -373,35 +2841,37 @@ mod Nft {
             id: TokenId,
             name: String,
             image: String,
-            has_discount: bool,
-            discount: String,
-            to: AccountId,
-        ) -> Result<(), Error> {
-            let caller = self.env().caller();
-            // chage that this is the owner of the nft set they are trying to deploy to
-            // add register constraint
-            let status = self.nft_set.get(&setid).map(|v| v.owner.clone());
-            if status == Some(caller) {
-                // require that set works
-                self.add_token_to_owner_enumeration(&to, id)?;
-                self.add_token_to(&to, id)?;
-                self.add_token_to_set_enumeration(setid, id);
-                self.token_features.insert(
-                    id,
-                    TokenFeatures {
-                        name,
-                        image,
-                        has_discount,
-                        discount,
-                        issuer: caller,
-                    },
-                );
-                self.env().transfer(to, self.env().transferred_balance());
-                self.env().emit_event(Transfer {
-                    from: Some(AccountId::from([0x0; 32])),
-                    to: Some(to),
-                    id,
-                });
+            discount: Option<DiscountTerms>,
+            attributes: Vec<(String, String)>,
+            to: AccountId,
+            price: Balance,
+        ) -> Result<(), Error> {
+            self.ensure_migrated()?;
+            self.ensure_not_paused()?;
+            let caller = self.env().caller();
+            // chage that this is the owner of the nft set they are trying to deploy to
+            // add register constraint
+            if self.can_mint_into(setid, caller) {
+                let owner = self.mintable_set_owner(setid, 1)?;
+                // require that set works
+                let template = TokenTemplate {
+                    name,
+                    image,
+                    discount,
+                    attributes,
+                };
+                validate_template(&template)?;
+                if self.is_taken(id) {
+                    return Err(Error::TokenExists);
+                }
+                if to == AccountId::from([0x0; 32]) {
+                    return Err(Error::NotAllowed);
+                }
+                // the price is paid by the caller in the currency of the set
+                let currency = self.payment_currency(setid);
+                self.collect_payment(currency, caller, price)?;
+                self.mint_token(setid, id, template, owner, to)?;
+                self.pay_with_fee(currency, to, price)?;
             } else {
                 return Err(Error::NotIssuer);
             }
@@ -422,12 +2892,27 @@ mod Nft {
             bidtokens: Vec<u32>,
             asktokens: Vec<u32>,
             to: AccountId,
+            price: Balance,
         ) -> Result<(), Error> {
+            self.ensure_migrated()?;
+            self.ensure_not_paused()?;
             let caller = self.env().caller();
+            // swap ids are never reused, so a pending proposal can't be replaced
+            if self.swaps.contains_key(&swapid) {
+                return Err(Error::SwapExists);
+            }
             // check that at that point in time you are the owner of those transactions
             let check_bid = self.is_owner_of_tokens(caller, bidtokens.clone());
             let check_ask = self.is_owner_of_tokens(to, asktokens.clone());
-            // check that swap id doesnt exist
+            if bidtokens
+                .iter()
+                .chain(asktokens.iter())
+                .any(|id| self.is_soulbound(*id))
+            {
+                return Err(Error::Soulbound);
+            }
+            // the price is paid in the currency shared by the sets of the bid tokens
+            let currency = self.currency_of_tokens(&bidtokens)?;
             if check_bid == true && check_ask == true {
                 // Approve the other person to make the transaction for your selected tokens
                 // add swap == true condition
@@ -441,6 +2926,8 @@ mod Nft {
                         bids: bidtokens.clone(),
                         asks: asktokens.clone(),
                         recepient: to,
+                        price,
+                        currency,
                     },
                 );
                 let ask_address_length = *self.nft_ask_swap_count.get(&to).unwrap_or(&0);
@@ -478,6 +2965,7 @@ mod Nft {
 
         #[ink(message)]
         pub fn reject_trade(&mut self, swapid: u32) -> Result<(), Error> {
+            self.ensure_migrated()?;
             let caller = self.env().caller();
             let maker = self
                 .swaps
@@ -499,28 +2987,55 @@ mod Nft {
             }
         }
 
-        #[ink(message)]
+        /// Accepts a swap proposal. If the maker asked for a price it has to be
+        /// transferred with the call, and is paid to the maker minus royalties.
+        #[ink(message, payable)]
         pub fn accept_trade(&mut self, swapid: u32) -> Result<(), Error> {
+            self.ensure_migrated()?;
+            self.ensure_not_paused()?;
             // if the swap is rejected, or already accepted it is removed from this index with the take function (to be tested for 100% certainty)
             let status = self.nft_ask_swap_index.contains_key(&swapid);
             if status == true {
                 let maker = self.swaps.get(&swapid).map(|v| v.maker.clone()).unwrap_or(Default::default());
                 let bid_tokens = self.swaps.get(&swapid).map(|v| v.bids.clone()).unwrap_or(Default::default());
                 let ask_tokens = self.swaps.get(&swapid).map(|v| v.asks.clone()).unwrap_or(Default::default());
+                let price = self.swaps.get(&swapid).map(|v| v.price).unwrap_or(0);
+                let currency = self.swaps.get(&swapid).and_then(|v| v.currency);
                 // let bids: Vec<_> = bid_tokens.iter().copied().collect();
                 // let asks: Vec<_> = ask_tokens.iter().copied().collect();
+                let recipient = self.swaps.get(&swapid).map(|v| v.recepient);
                 let caller = self.env().caller();
+                if recipient != Some(caller) {
+                    return Err(Error::NotAllowed);
+                }
                 let stat_1 = self.is_owner_of_tokens(maker,bid_tokens.clone());
                 let stat_2 = self.is_owner_of_tokens(caller,ask_tokens.clone());
                 if stat_1 == true && stat_2 == true {
+                    // tokens may have become soulbound since the proposal was made
+                    if bid_tokens
+                        .iter()
+                        .chain(ask_tokens.iter())
+                        .any(|id| self.is_soulbound(*id))
+                    {
+                        return Err(Error::Soulbound);
+                    }
+                    // the maker may have approved the bid tokens to a newer proposal since
+                    if bid_tokens
+                        .iter()
+                        .any(|id| !self.approved_or_owner(Some(caller), *id))
+                    {
+                        return Err(Error::NotApproved);
+                    }
+                    self.collect_payment(currency, caller, price)?;
                     // send the tokens of the caller
-                    for t in ask_tokens.clone().iter(){
-                        self.transfer_token_from(&caller,&maker,*t);
+                    for t in ask_tokens.clone().iter() {
+                        self.transfer_token_from(&caller, &maker, *t)?;
                     }
 
-                    for t in bid_tokens.clone().iter(){
-                        self.transfer_token_from(&maker,&caller,*t);
+                    for t in bid_tokens.clone().iter() {
+                        self.transfer_token_from(&maker, &caller, *t)?;
                     }
+                    self.pay_sale(currency, &bid_tokens, maker, price)?;
                     self.remove_swap_from_ask_enumeration(&caller, swapid);
                     self.remove_swap_from_bid_enumeration(&maker, swapid);
                     self.env().emit_event(SwapAccepted {
@@ -592,11 +3107,60 @@ mod Nft {
             destination: AccountId,
             id: TokenId,
         ) -> Result<(), Error> {
+            self.ensure_migrated()?;
+            self.ensure_not_paused()?;
             let caller = self.env().caller();
             self.transfer_token_from(&caller, &destination, id)?;
             Ok(())
         }
 
+        /// Transfers every `(destination, id)` entry of `transfers`. Nothing is
+        /// transferred if the caller can not transfer any of the tokens.
+        #[ink(message)]
+        pub fn transfer_batch(
+            &mut self,
+            transfers: Vec<(AccountId, TokenId)>,
+        ) -> Result<(), Error> {
+            self.ensure_migrated()?;
+            self.ensure_not_paused()?;
+            let caller = self.env().caller();
+            if transfers.len() > MAX_BATCH_LEN as usize {
+                return Err(Error::BatchTooLarge);
+            }
+            let mut ids = BTreeSet::new();
+            for (destination, id) in transfers.iter() {
+                if !self.exists(*id) {
+                    return Err(Error::TokenNotFound);
+                }
+                if !ids.insert(*id) || *destination == AccountId::from([0x0; 32]) {
+                    return Err(Error::NotAllowed);
+                }
+                if !self.approved_or_owner(Some(caller), *id) {
+                    return Err(Error::NotApproved);
+                }
+                if self.is_soulbound(*id) {
+                    return Err(Error::Soulbound);
+                }
+            }
+            for (destination, id) in transfers.into_iter() {
+                let owner = self.owner_of(id).ok_or(Error::TokenNotFound)?;
+                self.transfer_token_from(&owner, &destination, id)?;
+            }
+            Ok(())
+        }
+
+        /// Burns token `id` of the caller. Soulbound tokens can be burned too.
+        #[ink(message)]
+        pub fn burn(&mut self, id: TokenId) -> Result<(), Error> {
+            self.ensure_migrated()?;
+            self.ensure_not_paused()?;
+            let caller = self.env().caller();
+            if self.owner_of(id) != Some(caller) {
+                return Err(Error::NotOwner);
+            }
+            self.burn_token(&caller, id)
+        }
+
         // swap
 
         // propose swap
@@ -742,8 +3306,70 @@ mod Nft {
             Ok(())
         }
 
+        fn add_issuer_to_enumeration(&mut self, issuer: AccountId) {
+            let length = self.issuers_count;
+            self.issuers.insert(length, issuer);
+            self.issuers_index.insert(issuer, length);
+            self.issuers_count += 1;
+        }
+
+        fn remove_issuer_from_enumeration(
+            &mut self,
+            issuer: &AccountId,
+        ) -> Result<(), Error> {
+            let last_index = self.issuers_count - 1;
+            let index = self
+                .issuers_index
+                .take(issuer)
+                .ok_or(Error::CannotFetchValue)?;
+
+            // When the issuer to delete is the last issuer, the swap operation is unnecessary
+            if index != last_index {
+                let last_issuer = *self
+                    .issuers
+                    .get(&last_index)
+                    .ok_or(Error::CannotFetchValue)?;
+                self.issuers.insert(index, last_issuer);
+                self.issuers_index.insert(last_issuer, index);
+            }
+
+            self.issuers.take(&last_index);
+            self.issuers_count -= 1;
+            Ok(())
+        }
+
         // Decrement nft enumeration
 
+        pub fn remove_set_from_enumeration(
+            &mut self,
+            from: &AccountId,
+            setid: u32,
+        ) -> Result<(), Error> {
+            let last_set_index = self.issuer_set_balance(*from) - 1;
+            let set_index = *self.owned_nft_set_index.get(&setid).unwrap_or(&0);
+
+            // When the set to delete is the last set, the swap operation is unnecessary
+            if set_index != last_set_index {
+                let last_set_id = *self
+                    .owned_nft_set
+                    .get(&(*from, last_set_index))
+                    .ok_or(Error::CannotFetchValue)?;
+                self.owned_nft_set.insert((*from, set_index), last_set_id);
+                self.owned_nft_set_index.insert(last_set_id, set_index);
+            }
+
+            self.owned_nft_set_index.take(&setid);
+
+            self.owned_nft_set.take(&(*from, last_set_index));
+            let Self {
+                owned_nft_set_count,
+                ..
+            } = self;
+            decrease_counter_of(owned_nft_set_count, from)?;
+
+            Ok(())
+        }
+
         // pub fn increment_nft_set(&mut self, id: u32) -> Result<(), Error> {
         // let entry = *self.owned_nft_set_count.get_mut(&id).unwrap_or(&mut 0);
         // let new = entry+1;
@@ -891,17 +3517,585 @@ mod Nft {
             Ok(())
         }
 
-        /// Add token to enumeration list
-        // #[ink(message)]
-        // fn add_token_to_owner_enumeration(&mut self,to:AccountId,id:TokenId) -> Result<(),Error> {
-        // let length = self.balance_of(to);
-        // self.owned_tokens.insert((to,length),id);
-        // Ok(())
-        // // match self.operator_approvals.insert((caller, to), approved) {
-        // // Some(_) => Err(Error::CannotInsert),
-        // // None => Ok(()),
-        // // }
-        // }
+        /// Add token to enumeration list
+        // #[ink(message)]
+        // fn add_token_to_owner_enumeration(&mut self,to:AccountId,id:TokenId) -> Result<(),Error> {
+        // let length = self.balance_of(to);
+        // self.owned_tokens.insert((to,length),id);
+        // Ok(())
+        // // match self.operator_approvals.insert((caller, to), approved) {
+        // // Some(_) => Err(Error::CannotInsert),
+        // // None => Ok(()),
+        // // }
+        // }
+
+        /// Revokes every minter of the set.
+        fn clear_set_minters(&mut self, setid: u32) {
+            for index in 0..self.set_minter_balance(setid) {
+                if let Some(minter) = self.set_minters.take(&(setid, index)) {
+                    self.set_minters_index.take(&(setid, minter));
+                    self.env().emit_event(MinterRevoked { setid, minter });
+                }
+            }
+            self.set_minters_count.take(&setid);
+        }
+
+        /// Returns true if `account` owns the set, is one of its minters, or has the
+        /// minter role and the set allows minting by the role.
+        fn can_mint_into(&self, setid: u32, account: AccountId) -> bool {
+            let owner = self.nft_set.get(&setid).map(|v| v.owner.clone());
+            owner == Some(account)
+                || self.is_set_minter(setid, account)
+                || (self.role_minting(setid) && self.has_role(MINTER_ROLE, account))
+        }
+
+        /// Initializes the fields added in storage version 2 and moves the token
+        /// templates to their new encoding.
+        fn migrate_from_v1(&mut self) {
+            // the cells of the new maps are still empty, and an empty map header
+            // can't be loaded, so every map starts out fresh
+            self.council = Default::default();
+            self.council_index = Default::default();
+            Lazy::set(&mut self.council_count, 0);
+            Lazy::set(&mut self.council_threshold, 0);
+            self.proposals = Default::default();
+            Lazy::set(&mut self.proposals_count, 0);
+            self.proposal_approvals = Default::default();
+            self.set_templates = Default::default();
+            self.token_discounts = Default::default();
+            self.set_discount_windows = Default::default();
+            self.token_attributes = Default::default();
+            self.token_attribute_keys = Default::default();
+            self.token_attribute_keys_index = Default::default();
+            self.token_attribute_count = Default::default();
+            self.frozen_attributes = Default::default();
+            self.soulbound_sets = Default::default();
+            self.soulbound_tokens = Default::default();
+            self.issuer_merchants = Default::default();
+            self.issuer_merchants_index = Default::default();
+            self.issuer_merchants_count = Default::default();
+            self.set_minted = Default::default();
+            self.burned_tokens = Default::default();
+            self.discount_redemptions = Default::default();
+            self.role_minting_sets = Default::default();
+            // tokens could not be burned before version 2, so every minted token
+            // is still in its set
+            let supplies: Vec<(u32, u32)> = self
+                .owned_set_tokens_count
+                .iter()
+                .map(|(setid, count)| (*setid, *count))
+                .collect();
+            for (setid, count) in supplies {
+                self.set_minted.insert(setid, count);
+            }
+            let setids: Vec<u32> = self.legacy_set_templates.keys().cloned().collect();
+            for setid in setids {
+                if let Some(legacy) = self.legacy_set_templates.take(&setid) {
+                    let discount = if legacy.has_discount {
+                        Some(DiscountTerms {
+                            discount: Discount::Legacy(legacy.discount),
+                            valid_from: None,
+                            valid_until: None,
+                            remaining_uses: Some(1),
+                        })
+                    } else {
+                        None
+                    };
+                    self.set_templates.insert(
+                        setid,
+                        TokenTemplate {
+                            name: legacy.name,
+                            image: legacy.image,
+                            discount,
+                            attributes: Vec::new(),
+                        },
+                    );
+                }
+            }
+        }
+
+        fn ensure_migrated(&self) -> Result<(), Error> {
+            if self.storage_version() != STORAGE_VERSION {
+                return Err(Error::MigrationPending);
+            }
+            Ok(())
+        }
+
+        fn ensure_not_paused(&self) -> Result<(), Error> {
+            if self.paused {
+                return Err(Error::ContractPaused);
+            }
+            Ok(())
+        }
+
+        fn ensure_role(&self, role: RoleId, account: AccountId) -> Result<(), Error> {
+            if !self.has_role(role, account) {
+                return Err(Error::MissingRole);
+            }
+            Ok(())
+        }
+
+        /// Returns true if both the discount of token `id` and the discount window of
+        /// its set contain the current block time.
+        fn is_discount_in_window(&self, id: TokenId, terms: &DiscountTerms) -> bool {
+            let now = self.env().block_timestamp();
+            let set_window = self
+                .token_set
+                .get(&id)
+                .and_then(|setid| self.set_discount_windows.get(setid))
+                .cloned()
+                .unwrap_or((None, None));
+            is_within_window(terms.valid_from, terms.valid_until, now)
+                && is_within_window(set_window.0, set_window.1, now)
+        }
+
+        /// Checks that `account` issued token `id` and its attributes aren't frozen.
+        fn ensure_attributes_editable(
+            &self,
+            id: TokenId,
+            account: AccountId,
+        ) -> Result<(), Error> {
+            let issuer = self.token_features.get(&id).map(|v| v.issuer);
+            if issuer != Some(account) {
+                return Err(Error::NotIssuer);
+            }
+            if self.attributes_frozen(id) {
+                return Err(Error::AttributesFrozen);
+            }
+            Ok(())
+        }
+
+        /// Sets an attribute of a token, adding its key to the enumeration if it is new.
+        fn write_attribute(&mut self, id: TokenId, key: String, value: String) {
+            if !self.token_attributes.contains_key(&(id, key.clone())) {
+                let length = *self.token_attribute_count.get(&id).unwrap_or(&0);
+                self.token_attribute_keys.insert((id, length), key.clone());
+                self.token_attribute_keys_index
+                    .insert((id, key.clone()), length);
+                self.token_attribute_count.insert(id, length + 1);
+            }
+            self.token_attributes
+                .insert((id, key.clone()), value.clone());
+            self.env().emit_event(AttributeSet { id, key, value });
+        }
+
+        fn ensure_no_council(&self) -> Result<(), Error> {
+            if *self.council_threshold > 0 {
+                return Err(Error::CouncilRequired);
+            }
+            Ok(())
+        }
+
+        /// Returns proposal `id` if it can still be approved and executed.
+        fn open_proposal(&self, id: u32) -> Result<Proposal, Error> {
+            let proposal = self.get_proposal(id).ok_or(Error::ProposalNotFound)?;
+            if proposal.executed {
+                return Err(Error::NotAllowed);
+            }
+            if self.env().block_timestamp() > proposal.expires_at {
+                return Err(Error::ProposalExpired);
+            }
+            Ok(proposal)
+        }
+
+        fn mark_executed(&mut self, id: u32) {
+            if let Some(proposal) = self.proposals.get_mut(&id) {
+                proposal.executed = true;
+            }
+            self.env().emit_event(ProposalExecuted { id });
+        }
+
+        fn execute_action(
+            &mut self,
+            action: AdminAction,
+            sender: AccountId,
+        ) -> Result<(), Error> {
+            match action {
+                AdminAction::SetIssuer {
+                    issuer,
+                    name,
+                    status,
+                } => {
+                    self.write_issuer(issuer, name, status);
+                    Ok(())
+                }
+                AdminAction::RemoveIssuer { issuer } => self.delete_issuer(issuer),
+                AdminAction::TransferOwnership { new_owner } => {
+                    self.propose_owner(new_owner)
+                }
+                AdminAction::Pause => self.set_paused(true, sender),
+                AdminAction::Unpause => self.set_paused(false, sender),
+                AdminAction::SetFee { basis_points } => self.update_fee(basis_points),
+                AdminAction::WithdrawFees {
+                    currency,
+                    to,
+                    amount,
+                } => self.send_fees(currency, to, amount),
+                AdminAction::SetCouncil { members, threshold } => {
+                    self.replace_council(members, threshold)
+                }
+                AdminAction::Upgrade { code_hash } => self.set_code(code_hash),
+                AdminAction::GrantRole { role, account } => {
+                    self.grant_role_to(role, account, sender);
+                    Ok(())
+                }
+                AdminAction::RevokeRole { role, account } => {
+                    self.revoke_role_from(role, account, sender);
+                    Ok(())
+                }
+                AdminAction::SetRoleAdmin { role, admin_role } => {
+                    self.change_role_admin(role, admin_role);
+                    Ok(())
+                }
+            }
+        }
+
+        fn set_code(&mut self, code_hash: [u8; 32]) -> Result<(), Error> {
+            ink_env::set_code_hash(&code_hash).map_err(|_| Error::UpgradeFailed)?;
+            self.env().emit_event(Upgraded {
+                code_hash: Hash::from(code_hash),
+            });
+            Ok(())
+        }
+
+        fn change_role_admin(&mut self, role: RoleId, admin_role: RoleId) {
+            let previous_admin_role = self.get_role_admin(role);
+            self.role_admins.insert(role, admin_role);
+            self.env().emit_event(RoleAdminChanged {
+                role,
+                previous_admin_role,
+                new_admin_role: admin_role,
+            });
+        }
+
+        /// Replaces the council. An empty council with threshold 0 removes it and
+        /// hands admin actions back to the role holders.
+        fn replace_council(
+            &mut self,
+            members: Vec<AccountId>,
+            threshold: u32,
+        ) -> Result<(), Error> {
+            if members.len() > MAX_BATCH_LEN as usize {
+                return Err(Error::BatchTooLarge);
+            }
+            if members.iter().collect::<BTreeSet<_>>().len() != members.len() {
+                return Err(Error::NotAllowed);
+            }
+            let valid = if members.is_empty() {
+                threshold == 0
+            } else {
+                threshold > 0 && threshold as usize <= members.len()
+            };
+            if !valid {
+                return Err(Error::InvalidThreshold);
+            }
+            for index in 0..*self.council_count {
+                if let Some(member) = self.council.take(&index) {
+                    self.council_index.take(&member);
+                }
+            }
+            for (index, member) in members.iter().enumerate() {
+                self.council.insert(index as u32, *member);
+                self.council_index.insert(*member, index as u32);
+            }
+            Lazy::set(&mut self.council_count, members.len() as u32);
+            Lazy::set(&mut self.council_threshold, threshold);
+            self.env().emit_event(CouncilChanged { members, threshold });
+            Ok(())
+        }
+
+        fn write_issuer(&mut self, to: AccountId, name: String, status: bool) {
+            let old_status = self.is_issuer(to);
+            self.nft_issuer.insert(
+                to,
+                IssuerFeatures {
+                    name: name.clone(),
+                    status,
+                },
+            );
+            match old_status {
+                None => {
+                    self.add_issuer_to_enumeration(to);
+                    self.env().emit_event(IssuerAdded {
+                        issuer: to,
+                        name,
+                        status,
+                    });
+                }
+                Some(old_status) => {
+                    self.env().emit_event(IssuerUpdated {
+                        issuer: to,
+                        name,
+                        status,
+                    });
+                    if old_status != status {
+                        self.env()
+                            .emit_event(IssuerStatusChanged { issuer: to, status });
+                    }
+                }
+            }
+        }
+
+        fn delete_issuer(&mut self, to: AccountId) -> Result<(), Error> {
+            if self.nft_issuer.take(&to).is_none() {
+                return Err(Error::NotIssuer);
+            }
+            self.remove_issuer_from_enumeration(&to)?;
+            self.env().emit_event(IssuerRemoved { issuer: to });
+            Ok(())
+        }
+
+        fn propose_owner(&mut self, new_owner: AccountId) -> Result<(), Error> {
+            if new_owner == AccountId::from([0x0; 32]) {
+                return Err(Error::NotAllowed);
+            }
+            self.pending_owner = Some(new_owner);
+            self.env().emit_event(OwnershipTransferStarted {
+                previous_owner: self.contract_owner,
+                new_owner,
+            });
+            Ok(())
+        }
+
+        fn set_paused(&mut self, paused: bool, account: AccountId) -> Result<(), Error> {
+            if paused {
+                self.ensure_not_paused()?;
+                self.paused = true;
+                self.env().emit_event(Paused { account });
+            } else {
+                if !self.paused {
+                    return Err(Error::NotAllowed);
+                }
+                self.paused = false;
+                self.env().emit_event(Unpaused { account });
+            }
+            Ok(())
+        }
+
+        fn update_fee(&mut self, basis_points: u16) -> Result<(), Error> {
+            if basis_points > MAX_BASIS_POINTS {
+                return Err(Error::InvalidBasisPoints);
+            }
+            let old_fee = self.protocol_fee;
+            self.protocol_fee = basis_points;
+            self.env().emit_event(FeeUpdated {
+                old_fee,
+                new_fee: basis_points,
+            });
+            Ok(())
+        }
+
+        /// Withdraws `amount` of the protocol fees accrued in `currency` to `to`.
+        fn send_fees(
+            &mut self,
+            currency: Option<AccountId>,
+            to: AccountId,
+            amount: Balance,
+        ) -> Result<(), Error> {
+            match currency {
+                None => {
+                    if amount > self.accrued_fees {
+                        return Err(Error::InsufficientFees);
+                    }
+                    self.accrued_fees -= amount;
+                    self.env()
+                        .transfer(to, amount)
+                        .map_err(|_| Error::TransferFailed)?;
+                }
+                Some(currency) => {
+                    let accrued = self.accrued_token_fees(currency);
+                    if amount > accrued {
+                        return Err(Error::InsufficientFees);
+                    }
+                    self.accrued_token_fees.insert(currency, accrued - amount);
+                    self.psp22_transfer(currency, to, amount)?;
+                }
+            }
+            self.env().emit_event(FeesWithdrawn {
+                currency,
+                to,
+                amount,
+            });
+            Ok(())
+        }
+
+        fn grant_role_to(&mut self, role: RoleId, account: AccountId, sender: AccountId) {
+            if self.has_role(role, account) {
+                return;
+            }
+            self.roles.insert((role, account), true);
+            self.env().emit_event(RoleGranted {
+                role,
+                account,
+                sender,
+            });
+        }
+
+        fn revoke_role_from(
+            &mut self,
+            role: RoleId,
+            account: AccountId,
+            sender: AccountId,
+        ) {
+            if self.roles.take(&(role, account)).is_none() {
+                return;
+            }
+            self.env().emit_event(RoleRevoked {
+                role,
+                account,
+                sender,
+            });
+        }
+
+        /// Returns the owner of the set if `quantity` more tokens can be minted into
+        /// it, which requires both the set and the issuer owning it to be active, and
+        /// the set to be unsealed with room left below its maximum supply.
+        fn mintable_set_owner(
+            &self,
+            setid: u32,
+            quantity: u32,
+        ) -> Result<AccountId, Error> {
+            let set = self.nft_set.get(&setid).ok_or(Error::NotIssuer)?;
+            if !set.status {
+                return Err(Error::SetInactive);
+            }
+            if set.sealed {
+                return Err(Error::SetSealed);
+            }
+            if let Some(max_supply) = set.max_supply {
+                // burned tokens keep counting, so burning never frees up supply
+                let supply = self.nft_set_minted(setid);
+                if supply.saturating_add(quantity) > max_supply {
+                    return Err(Error::SupplyExceeded);
+                }
+            }
+            if self.is_issuer(set.owner) != Some(true) {
+                return Err(Error::NotIssuer);
+            }
+            Ok(set.owner)
+        }
+
+        /// Mints token `id` into the set `setid` for `to`, with `issuer` recorded as
+        /// the issuer of the token.
+        fn mint_token(
+            &mut self,
+            setid: u32,
+            id: TokenId,
+            template: TokenTemplate,
+            issuer: AccountId,
+            to: AccountId,
+        ) -> Result<(), Error> {
+            if self.is_taken(id) {
+                return Err(Error::TokenExists);
+            }
+            if to == AccountId::from([0x0; 32]) {
+                return Err(Error::NotAllowed);
+            }
+            self.add_token_to_owner_enumeration(&to, id)?;
+            self.add_token_to(&to, id)?;
+            self.add_token_to_set_enumeration(setid, id)?;
+            self.token_set.insert(id, setid);
+            let minted = self.nft_set_minted(setid);
+            self.set_minted.insert(setid, minted + 1);
+            self.token_features.insert(
+                id,
+                TokenFeatures {
+                    name: template.name,
+                    image: template.image,
+                    has_discount: template.discount.is_some(),
+                    discount: String::new(),
+                    issuer,
+                },
+            );
+            if let Some(terms) = template.discount {
+                self.token_discounts.insert(id, terms);
+            }
+            self.env().emit_event(Transfer {
+                from: Some(AccountId::from([0x0; 32])),
+                to: Some(to),
+                id,
+            });
+            for (key, value) in template.attributes.into_iter() {
+                self.write_attribute(id, key, value);
+            }
+            Ok(())
+        }
+
+        /// Returns the number of tokens `buyer` bought from the sale of the set, through
+        /// the allowlist and in the public sale together.
+        fn bought_from_sale(&self, setid: u32, buyer: AccountId) -> u32 {
+            self.purchased(setid, buyer)
+                .saturating_add(self.allowlist_claimed(setid, buyer))
+        }
+
+        /// Sells `quantity` tokens from the sale of the set to `buyer`. The tokens are
+        /// minted with sequential ids and the payment goes to the owner of the set.
+        fn sell_tokens(
+            &mut self,
+            setid: u32,
+            buyer: AccountId,
+            quantity: u32,
+        ) -> Result<(), Error> {
+            let sale = self.set_sales.get(&setid).cloned().ok_or(Error::NoSale)?;
+            if quantity == 0 {
+                return Err(Error::InvalidQuantity);
+            }
+            if quantity > MAX_BATCH_LEN {
+                return Err(Error::BatchTooLarge);
+            }
+            if sale.sold.saturating_add(quantity) > sale.max_supply {
+                return Err(Error::SoldOut);
+            }
+            let template = self.token_template(setid).ok_or(Error::NoTemplate)?;
+            let owner = self.mintable_set_owner(setid, quantity)?;
+            let total = sale
+                .price
+                .checked_mul(quantity as Balance)
+                .ok_or(Error::IncorrectPayment)?;
+            let currency = self.payment_currency(setid);
+            self.collect_payment(currency, buyer, total)?;
+
+            for _ in 0..quantity {
+                let id = self.next_free_token_id();
+                self.mint_token(setid, id, template.clone(), owner, buyer)?;
+            }
+            if let Some(sale) = self.set_sales.get_mut(&setid) {
+                sale.sold += quantity;
+            }
+            self.pay_with_fee(currency, owner, total)
+        }
+
+        /// Returns true if `proof` proves `leaf` is part of the Merkle tree with
+        /// `root`. Pairs of nodes are sorted before they are hashed together.
+        fn verify_merkle_proof(
+            &self,
+            root: Hash,
+            leaf: [u8; 32],
+            proof: &[Hash],
+        ) -> bool {
+            let mut computed = leaf;
+            for node in proof.iter() {
+                let node: &[u8] = node.as_ref();
+                let mut pair = [0u8; 64];
+                if computed[..] <= *node {
+                    pair[..32].copy_from_slice(&computed);
+                    pair[32..].copy_from_slice(node);
+                } else {
+                    pair[..32].copy_from_slice(node);
+                    pair[32..].copy_from_slice(&computed);
+                }
+                computed = self.env().hash_bytes::<Blake2x256>(&pair);
+            }
+            Hash::from(computed) == root
+        }
+
+        /// Returns the next token id for sequential minting that was never used.
+        fn next_free_token_id(&mut self) -> TokenId {
+            while self.is_taken(self.next_token_id) {
+                self.next_token_id += 1;
+            }
+            self.next_token_id
+        }
 
         /// Transfers token `id` `from` the sender to the `to` AccountId.
         fn transfer_token_from(
@@ -917,6 +4111,9 @@ mod Nft {
             if !self.approved_or_owner(Some(caller), id) {
                 return Err(Error::NotApproved);
             };
+            if self.is_soulbound(id) {
+                return Err(Error::Soulbound);
+            }
             self.clear_approval(id)?;
             self.remove_token_from_owner_enumeration(from,id)?;
             self.remove_token_from(from, id)?;
@@ -930,6 +4127,61 @@ mod Nft {
             Ok(())
         }
 
+        /// Burns token `id` of `owner` and removes everything stored about it.
+        fn burn_token(&mut self, owner: &AccountId, id: TokenId) -> Result<(), Error> {
+            self.clear_approval(id)?;
+            self.remove_token_from_owner_enumeration(owner, id)?;
+            self.remove_token_from(owner, id)?;
+            if let Some(setid) = self.token_set.take(&id) {
+                self.remove_token_from_set_enumeration(setid, id)?;
+            }
+            let attribute_count = *self.token_attribute_count.get(&id).unwrap_or(&0);
+            for index in 0..attribute_count {
+                if let Some(key) = self.token_attribute_keys.take(&(id, index)) {
+                    self.token_attribute_keys_index.take(&(id, key.clone()));
+                    self.token_attributes.take(&(id, key));
+                }
+            }
+            self.token_attribute_count.take(&id);
+            self.frozen_attributes.take(&id);
+            self.token_features.take(&id);
+            self.token_discounts.take(&id);
+            self.discount_redemptions.take(&id);
+            self.token_royalty.take(&id);
+            self.soulbound_tokens.take(&id);
+            self.burned_tokens.insert(id, true);
+            self.env().emit_event(Transfer {
+                from: Some(*owner),
+                to: Some(AccountId::from([0x0; 32])),
+                id,
+            });
+            Ok(())
+        }
+
+        fn remove_token_from_set_enumeration(
+            &mut self,
+            setid: u32,
+            id: TokenId,
+        ) -> Result<(), Error> {
+            let last_index = self.nft_set_balance(setid) - 1;
+            let index = self
+                .owned_set_tokens_index
+                .take(&id)
+                .ok_or(Error::CannotFetchValue)?;
+            // When the token to delete is the last token, the swap operation is unnecessary
+            if index != last_index {
+                let last_token = *self
+                    .owned_set_tokens
+                    .get(&(setid, last_index))
+                    .ok_or(Error::CannotFetchValue)?;
+                self.owned_set_tokens.insert((setid, index), last_token);
+                self.owned_set_tokens_index.insert(last_token, index);
+            }
+            self.owned_set_tokens.take(&(setid, last_index));
+            self.owned_set_tokens_count.insert(setid, last_index);
+            Ok(())
+        }
+
         /// Removes token `id` from the owner.
         fn remove_token_from(
             &mut self,
@@ -1062,10 +4314,211 @@ mod Nft {
                     ))
         }
 
+        /// Returns the currency shared by the sets of `tokens`.
+        fn currency_of_tokens(
+            &self,
+            tokens: &[TokenId],
+        ) -> Result<Option<AccountId>, Error> {
+            let mut currencies = tokens.iter().map(|id| {
+                self.token_set
+                    .get(id)
+                    .and_then(|setid| self.set_currency.get(setid))
+                    .cloned()
+            });
+            let currency = currencies.next().unwrap_or(None);
+            if currencies.any(|c| c != currency) {
+                return Err(Error::CurrencyMismatch);
+            }
+            Ok(currency)
+        }
+
+        /// Receives `amount` of `currency` from `from` into the contract. Native
+        /// payments have to be transferred with the call, PSP22 payments are pulled
+        /// from the allowance `from` gave to this contract.
+        fn collect_payment(
+            &mut self,
+            currency: Option<AccountId>,
+            from: AccountId,
+            amount: Balance,
+        ) -> Result<(), Error> {
+            match currency {
+                None => {
+                    if self.env().transferred_balance() != amount {
+                        return Err(Error::IncorrectPayment);
+                    }
+                    Ok(())
+                }
+                Some(token) => {
+                    if self.env().transferred_balance() != 0 {
+                        return Err(Error::CurrencyMismatch);
+                    }
+                    if amount == 0 {
+                        return Ok(());
+                    }
+                    let contract = self.env().account_id();
+                    self.psp22_transfer_from(token, from, contract, amount)
+                }
+            }
+        }
+
+        /// Pays `amount` of `currency` held by the contract to `to`.
+        fn pay(
+            &mut self,
+            currency: Option<AccountId>,
+            to: AccountId,
+            amount: Balance,
+        ) -> Result<(), Error> {
+            match currency {
+                None => self
+                    .env()
+                    .transfer(to, amount)
+                    .map_err(|_| Error::TransferFailed),
+                Some(token) => self.psp22_transfer(token, to, amount),
+            }
+        }
+
+        /// Calls `PSP22::transfer` on `token` to move `amount` from this contract to `to`.
+        fn psp22_transfer(
+            &self,
+            token: AccountId,
+            to: AccountId,
+            amount: Balance,
+        ) -> Result<(), Error> {
+            self.call_psp22(
+                token,
+                ExecutionInput::new(Selector::new(PSP22_TRANSFER_SELECTOR))
+                    .push_arg(to)
+                    .push_arg(amount)
+                    .push_arg(Vec::<u8>::new()),
+            )
+        }
+
+        /// Calls `PSP22::transfer_from` on `token` to move `amount` from `from` to `to`.
+        fn psp22_transfer_from(
+            &self,
+            token: AccountId,
+            from: AccountId,
+            to: AccountId,
+            amount: Balance,
+        ) -> Result<(), Error> {
+            self.call_psp22(
+                token,
+                ExecutionInput::new(Selector::new(PSP22_TRANSFER_FROM_SELECTOR))
+                    .push_arg(from)
+                    .push_arg(to)
+                    .push_arg(amount)
+                    .push_arg(Vec::<u8>::new()),
+            )
+        }
+
+        /// Calls the PSP22 contract `token` with `input`, for messages returning
+        /// `Result<(), PSP22Error>`.
+        #[cfg(not(test))]
+        fn call_psp22<Args: scale::Encode>(
+            &self,
+            token: AccountId,
+            input: ExecutionInput<Args>,
+        ) -> Result<(), Error> {
+            build_call::<ink_env::DefaultEnvironment>()
+                .callee(token)
+                .gas_limit(0)
+                .transferred_value(0)
+                .exec_input(input)
+                .returns::<ReturnType<Result<(), PSP22Error>>>()
+                .fire()
+                .map_err(|_| Error::TransferFailed)?
+                .map_err(|_| Error::TransferFailed)
+        }
+
+        /// The off-chain environment can't call other contracts, so in tests the
+        /// encoded call goes to the mock PSP22 token of the test module instead.
+        #[cfg(test)]
+        fn call_psp22<Args: scale::Encode>(
+            &self,
+            token: AccountId,
+            input: ExecutionInput<Args>,
+        ) -> Result<(), Error> {
+            tests::psp22::call(token, self.env().account_id(), &input.encode())
+                .map_err(|_| Error::TransferFailed)
+        }
+
+        /// Keeps the protocol fee of `amount` in the contract and returns it.
+        fn take_fee(&mut self, currency: Option<AccountId>, amount: Balance) -> Balance {
+            let fee = basis_points_of(amount, self.protocol_fee);
+            if fee > 0 {
+                match currency {
+                    None => self.accrued_fees += fee,
+                    Some(token) => {
+                        let accrued = self.accrued_token_fees(token);
+                        self.accrued_token_fees.insert(token, accrued + fee);
+                    }
+                }
+                self.env().emit_event(FeeCollected {
+                    currency,
+                    amount: fee,
+                });
+            }
+            fee
+        }
+
+        /// Pays `amount` of `currency` to `to` after keeping the protocol fee.
+        fn pay_with_fee(
+            &mut self,
+            currency: Option<AccountId>,
+            to: AccountId,
+            amount: Balance,
+        ) -> Result<(), Error> {
+            if amount == 0 {
+                return Ok(());
+            }
+            let fee = self.take_fee(currency, amount);
+            self.pay(currency, to, amount - fee)
+        }
+
+        /// Pays `price` for the sold `tokens` to `seller`, after splitting off the
+        /// protocol fee and the royalty of each token. The price is shared equally
+        /// between the tokens.
+        fn pay_sale(
+            &mut self,
+            currency: Option<AccountId>,
+            tokens: &[TokenId],
+            seller: AccountId,
+            price: Balance,
+        ) -> Result<(), Error> {
+            if price == 0 {
+                return Ok(());
+            }
+            let mut proceeds = price - self.take_fee(currency, price);
+            if !tokens.is_empty() {
+                let share = price / tokens.len() as Balance;
+                for id in tokens.iter() {
+                    if let Some((receiver, amount)) = self.royalty_info(*id, share) {
+                        let amount = amount.min(proceeds);
+                        if amount == 0 {
+                            continue;
+                        }
+                        self.pay(currency, receiver, amount)?;
+                        proceeds -= amount;
+                        self.env().emit_event(RoyaltyPaid {
+                            receiver,
+                            id: *id,
+                            amount,
+                        });
+                    }
+                }
+            }
+            self.pay(currency, seller, proceeds)
+        }
+
         /// Returns true if token `id` exists or false if it does not.
         fn exists(&self, id: TokenId) -> bool {
             self.token_owner.get(&id).is_some() && self.token_owner.contains_key(&id)
         }
+
+        /// Returns true if token `id` exists or was burned, so it can't be minted.
+        fn is_taken(&self, id: TokenId) -> bool {
+            self.exists(id) || self.burned_tokens.contains_key(&id)
+        }
     }
 
     // Repear this for
@@ -1085,6 +4538,1305 @@ mod Nft {
     fn increase_counter_of(entry: Entry<AccountId, u32>) {
         entry.and_modify(|v| *v += 1).or_insert(1);
     }
+
+    /// Checks that the attributes of a template are within bounds and that the
+    /// discount can be used at some point in time.
+    fn validate_template(template: &TokenTemplate) -> Result<(), Error> {
+        if template.attributes.len() > MAX_ATTRIBUTES as usize {
+            return Err(Error::TooManyAttributes);
+        }
+        let mut keys = BTreeSet::new();
+        for (key, value) in template.attributes.iter() {
+            validate_attribute(key, value)?;
+            if !keys.insert(key) {
+                return Err(Error::InvalidAttribute);
+            }
+        }
+        let terms = match &template.discount {
+            Some(terms) => terms,
+            None => return Ok(()),
+        };
+        let valid = match &terms.discount {
+            Discount::Percentage { basis_points } => *basis_points <= MAX_BASIS_POINTS,
+            Discount::FixedAmount {
+                amount,
+                currency_code,
+            } => *amount > 0 && !currency_code.is_empty(),
+            Discount::BuyXGetY { buy, get } => *buy > 0 && *get > 0,
+            Discount::FreeItem { sku } => !sku.is_empty(),
+            Discount::Legacy(_) => false,
+        };
+        if !valid || terms.remaining_uses == Some(0) {
+            return Err(Error::InvalidDiscount);
+        }
+        match (terms.valid_from, terms.valid_until) {
+            (Some(from), Some(until)) if from >= until => Err(Error::InvalidDiscount),
+            _ => Ok(()),
+        }
+    }
+
+    /// Checks that an attribute has a key and both key and value fit their bounds.
+    fn validate_attribute(key: &str, value: &str) -> Result<(), Error> {
+        if key.is_empty()
+            || key.len() > MAX_ATTRIBUTE_KEY_LEN
+            || value.len() > MAX_ATTRIBUTE_VALUE_LEN
+        {
+            return Err(Error::InvalidAttribute);
+        }
+        Ok(())
+    }
+
+    /// Returns true if `now` falls in the window from `valid_from` until `valid_until`.
+    fn is_within_window(
+        valid_from: Option<Timestamp>,
+        valid_until: Option<Timestamp>,
+        now: Timestamp,
+    ) -> bool {
+        valid_from.map_or(true, |from| now >= from)
+            && valid_until.map_or(true, |until| now < until)
+    }
+
+    /// Stores `version` as the version of the layout the stored state is in.
+    fn write_storage_version(version: u32) {
+        ink_env::set_contract_storage(&Key::from(STORAGE_VERSION_KEY), &version);
+    }
+
+    /// Returns the share of `amount` expressed by `basis_points`.
+    fn basis_points_of(amount: Balance, basis_points: u16) -> Balance {
+        let basis_points = basis_points as Balance;
+        let max = MAX_BASIS_POINTS as Balance;
+        amount / max * basis_points + amount % max * basis_points / max
+    }
+
+    /// Unit tests
+    #[cfg(test)]
+    mod tests {
+        /// Imports all the definitions from the outer scope so we can use them here.
+        use super::*;
+        use ink_env::{call, test, DefaultEnvironment};
+        use ink_lang as ink;
+        use ink_primitives::KeyPtr;
+
+        const SET: u32 = 1;
+
+        /// PSP22 token the contract calls instead of a deployed contract, keeping the
+        /// balances and allowances of every token for the running test.
+        pub mod psp22 {
+            use super::super::*;
+            use std::cell::RefCell;
+            use std::collections::BTreeMap;
+
+            #[derive(Default)]
+            struct Ledger {
+                balances: BTreeMap<(AccountId, AccountId), Balance>,
+                allowances: BTreeMap<(AccountId, AccountId, AccountId), Balance>,
+            }
+
+            thread_local! {
+                static LEDGER: RefCell<Ledger> = RefCell::new(Ledger::default());
+            }
+
+            pub fn reset() {
+                LEDGER.with(|ledger| *ledger.borrow_mut() = Ledger::default());
+            }
+
+            pub fn balance_of(token: AccountId, owner: AccountId) -> Balance {
+                LEDGER.with(|ledger| {
+                    *ledger.borrow().balances.get(&(token, owner)).unwrap_or(&0)
+                })
+            }
+
+            pub fn mint(token: AccountId, to: AccountId, amount: Balance) {
+                let balance = balance_of(token, to);
+                LEDGER.with(|ledger| {
+                    ledger
+                        .borrow_mut()
+                        .balances
+                        .insert((token, to), balance + amount)
+                });
+            }
+
+            pub fn approve(
+                token: AccountId,
+                owner: AccountId,
+                spender: AccountId,
+                amount: Balance,
+            ) {
+                LEDGER.with(|ledger| {
+                    ledger
+                        .borrow_mut()
+                        .allowances
+                        .insert((token, owner, spender), amount)
+                });
+            }
+
+            fn move_balance(
+                token: AccountId,
+                from: AccountId,
+                to: AccountId,
+                amount: Balance,
+            ) -> Result<(), PSP22Error> {
+                let from_balance = balance_of(token, from);
+                if from_balance < amount {
+                    return Err(PSP22Error::InsufficientBalance);
+                }
+                let to_balance = balance_of(token, to);
+                LEDGER.with(|ledger| {
+                    let mut ledger = ledger.borrow_mut();
+                    ledger.balances.insert((token, from), from_balance - amount);
+                    ledger.balances.insert((token, to), to_balance + amount);
+                });
+                Ok(())
+            }
+
+            /// Executes the SCALE encoded message `input` that `caller` sent to `token`.
+            /// Panics on selectors the contract is not supposed to call.
+            pub fn call(
+                token: AccountId,
+                caller: AccountId,
+                input: &[u8],
+            ) -> Result<(), PSP22Error> {
+                let (selector, mut args) = input.split_at(4);
+                if selector == PSP22_TRANSFER_SELECTOR {
+                    let (to, amount, _data) =
+                        <(AccountId, Balance, Vec<u8>)>::decode(&mut args)
+                            .expect("invalid transfer input");
+                    move_balance(token, caller, to, amount)
+                } else if selector == PSP22_TRANSFER_FROM_SELECTOR {
+                    let (from, to, amount, _data) =
+                        <(AccountId, AccountId, Balance, Vec<u8>)>::decode(&mut args)
+                            .expect("invalid transfer_from input");
+                    let allowance = LEDGER.with(|ledger| {
+                        *ledger
+                            .borrow()
+                            .allowances
+                            .get(&(token, from, caller))
+                            .unwrap_or(&0)
+                    });
+                    if allowance < amount {
+                        return Err(PSP22Error::InsufficientAllowance);
+                    }
+                    move_balance(token, from, to, amount)?;
+                    approve(token, from, caller, allowance - amount);
+                    Ok(())
+                } else {
+                    panic!("unexpected PSP22 selector {:?}", selector)
+                }
+            }
+        }
+
+        fn accounts() -> test::DefaultAccounts<DefaultEnvironment> {
+            test::default_accounts::<DefaultEnvironment>().expect("Cannot get accounts")
+        }
+
+        fn contract_id() -> AccountId {
+            ink_env::account_id::<DefaultEnvironment>().unwrap_or([0x0; 32].into())
+        }
+
+        fn balance(account: AccountId) -> Balance {
+            test::get_account_balance::<DefaultEnvironment>(account)
+                .expect("Cannot get balance")
+        }
+
+        /// Makes `sender` the caller of the next messages, transferring `value`.
+        fn set_sender(sender: AccountId, value: Balance) {
+            test::push_execution_context::<DefaultEnvironment>(
+                sender,
+                contract_id(),
+                1000000,
+                value,
+                test::CallData::new(call::Selector::new([0x00; 4])), // dummy
+            );
+        }
+
+        fn template(name: &str) -> TokenTemplate {
+            TokenTemplate {
+                name: String::from(name),
+                image: String::from("ipfs://image"),
+                discount: None,
+                attributes: Vec::new(),
+            }
+        }
+
+        /// Deploys the contract as alice, with bob as the issuer of set `SET`.
+        fn deploy() -> Erc721 {
+            let accounts = accounts();
+            psp22::reset();
+            set_sender(accounts.alice, 0);
+            let mut erc721 = Erc721::new();
+            test::set_account_balance::<DefaultEnvironment>(contract_id(), 1_000_000);
+            assert_eq!(
+                erc721.set_issuer(accounts.bob, String::from("bob"), true),
+                Ok(())
+            );
+            set_sender(accounts.bob, 0);
+            assert_eq!(
+                erc721.create_nft_set(
+                    SET,
+                    String::from("set"),
+                    String::from("description"),
+                    true
+                ),
+                Ok(())
+            );
+            erc721
+        }
+
+        /// Mints token `id` into `SET` for `to` as bob, free of charge.
+        fn mint_to(erc721: &mut Erc721, id: TokenId, to: AccountId) {
+            set_sender(accounts().bob, 0);
+            assert_eq!(
+                erc721.mint(
+                    SET,
+                    id,
+                    String::from("token"),
+                    String::from("ipfs://image"),
+                    None,
+                    Vec::new(),
+                    to,
+                    0
+                ),
+                Ok(())
+            );
+        }
+
+        fn now() -> Timestamp {
+            ink_env::block_timestamp::<DefaultEnvironment>()
+                .expect("Cannot get block timestamp")
+        }
+
+        /// Gives `SET` a token template and opens a sale of it as bob.
+        fn open_sale(
+            erc721: &mut Erc721,
+            price: Balance,
+            max_supply: u32,
+            wallet_limit: u32,
+            start: Timestamp,
+            end: Timestamp,
+        ) {
+            set_sender(accounts().bob, 0);
+            if erc721.token_template(SET).is_none() {
+                assert_eq!(erc721.set_token_template(SET, template("sale")), Ok(()));
+            }
+            assert_eq!(
+                erc721.set_sale(SET, price, max_supply, wallet_limit, start, end),
+                Ok(())
+            );
+        }
+
+        /// Account of the mock PSP22 token.
+        fn token() -> AccountId {
+            AccountId::from([0x22; 32])
+        }
+
+        /// Makes `SET` paid in the mock PSP22 token.
+        fn use_psp22(erc721: &mut Erc721) {
+            set_sender(accounts().bob, 0);
+            assert_eq!(erc721.set_payment_currency(SET, Some(token())), Ok(()));
+        }
+
+        #[ink::test]
+        fn mint_pays_price_minus_fee() {
+            let accounts = accounts();
+            let mut erc721 = deploy();
+            set_sender(accounts.alice, 0);
+            assert_eq!(erc721.set_fee(500), Ok(()));
+            let before = balance(accounts.django);
+            set_sender(accounts.bob, 1000);
+            assert_eq!(
+                erc721.mint(
+                    SET,
+                    1,
+                    String::from("token"),
+                    String::from("ipfs://image"),
+                    None,
+                    Vec::new(),
+                    accounts.django,
+                    1000
+                ),
+                Ok(())
+            );
+            assert_eq!(erc721.owner_of(1), Some(accounts.django));
+            assert_eq!(erc721.accrued_fees(), 50);
+            assert_eq!(balance(accounts.django), before + 950);
+        }
+
+        #[ink::test]
+        fn mint_rejects_wrong_payment() {
+            let accounts = accounts();
+            let mut erc721 = deploy();
+            set_sender(accounts.bob, 999);
+            assert_eq!(
+                erc721.mint(
+                    SET,
+                    1,
+                    String::from("token"),
+                    String::from("ipfs://image"),
+                    None,
+                    Vec::new(),
+                    accounts.django,
+                    1000
+                ),
+                Err(Error::IncorrectPayment)
+            );
+            assert_eq!(erc721.owner_of(1), None);
+            // sets paid in a PSP22 token do not accept the native currency
+            set_sender(accounts.bob, 0);
+            assert_eq!(
+                erc721.set_payment_currency(SET, Some(accounts.frank)),
+                Ok(())
+            );
+            set_sender(accounts.bob, 1000);
+            assert_eq!(
+                erc721.mint(
+                    SET,
+                    1,
+                    String::from("token"),
+                    String::from("ipfs://image"),
+                    None,
+                    Vec::new(),
+                    accounts.django,
+                    1000
+                ),
+                Err(Error::CurrencyMismatch)
+            );
+            assert_eq!(erc721.owner_of(1), None);
+            assert_eq!(erc721.nft_set_balance(SET), 0);
+        }
+
+        #[ink::test]
+        fn psp22_selectors_match_the_standard() {
+            let mut transfer = [0u8; 32];
+            ink_env::hash_bytes::<Blake2x256>(b"PSP22::transfer", &mut transfer);
+            assert_eq!(transfer[..4], PSP22_TRANSFER_SELECTOR);
+            let mut transfer_from = [0u8; 32];
+            ink_env::hash_bytes::<Blake2x256>(
+                b"PSP22::transfer_from",
+                &mut transfer_from,
+            );
+            assert_eq!(transfer_from[..4], PSP22_TRANSFER_FROM_SELECTOR);
+        }
+
+        #[ink::test]
+        fn mint_pulls_psp22_payment() {
+            let accounts = accounts();
+            let mut erc721 = deploy();
+            use_psp22(&mut erc721);
+            set_sender(accounts.alice, 0);
+            assert_eq!(erc721.set_fee(500), Ok(()));
+            psp22::mint(token(), accounts.bob, 1000);
+            psp22::approve(token(), accounts.bob, contract_id(), 999);
+
+            let mint = |erc721: &mut Erc721| {
+                set_sender(accounts.bob, 0);
+                erc721.mint(
+                    SET,
+                    1,
+                    String::from("token"),
+                    String::from("ipfs://image"),
+                    None,
+                    Vec::new(),
+                    accounts.django,
+                    1000,
+                )
+            };
+            assert_eq!(mint(&mut erc721), Err(Error::TransferFailed));
+            assert_eq!(erc721.owner_of(1), None);
+            assert_eq!(psp22::balance_of(token(), accounts.bob), 1000);
+
+            psp22::approve(token(), accounts.bob, contract_id(), 1000);
+            assert_eq!(mint(&mut erc721), Ok(()));
+            assert_eq!(erc721.owner_of(1), Some(accounts.django));
+            assert_eq!(psp22::balance_of(token(), accounts.bob), 0);
+            assert_eq!(psp22::balance_of(token(), accounts.django), 950);
+            assert_eq!(psp22::balance_of(token(), contract_id()), 50);
+            assert_eq!(erc721.accrued_token_fees(token()), 50);
+            assert_eq!(erc721.accrued_fees(), 0);
+        }
+
+        #[ink::test]
+        fn psp22_fees_are_withdrawn_in_the_token() {
+            let accounts = accounts();
+            let mut erc721 = deploy();
+            use_psp22(&mut erc721);
+            set_sender(accounts.alice, 0);
+            assert_eq!(erc721.set_fee(1000), Ok(()));
+            psp22::mint(token(), accounts.bob, 500);
+            psp22::approve(token(), accounts.bob, contract_id(), 500);
+            set_sender(accounts.bob, 0);
+            assert_eq!(
+                erc721.mint(
+                    SET,
+                    1,
+                    String::from("token"),
+                    String::from("ipfs://image"),
+                    None,
+                    Vec::new(),
+                    accounts.django,
+                    500
+                ),
+                Ok(())
+            );
+            assert_eq!(erc721.accrued_token_fees(token()), 50);
+
+            assert_eq!(
+                erc721.withdraw_token_fees(token(), accounts.bob, 50),
+                Err(Error::MissingRole)
+            );
+            set_sender(accounts.alice, 0);
+            assert_eq!(
+                erc721.withdraw_token_fees(token(), accounts.eve, 51),
+                Err(Error::InsufficientFees)
+            );
+            assert_eq!(
+                erc721.withdraw_token_fees(token(), accounts.eve, 30),
+                Ok(())
+            );
+            assert_eq!(erc721.accrued_token_fees(token()), 20);
+            assert_eq!(psp22::balance_of(token(), accounts.eve), 30);
+            assert_eq!(psp22::balance_of(token(), contract_id()), 20);
+        }
+
+        #[ink::test]
+        fn accept_trade_pays_in_psp22() {
+            let accounts = accounts();
+            let mut erc721 = deploy();
+            use_psp22(&mut erc721);
+            set_sender(accounts.alice, 0);
+            assert_eq!(erc721.set_fee(500), Ok(()));
+            set_sender(accounts.bob, 0);
+            assert_eq!(erc721.set_royalty(SET, accounts.charlie, 1000), Ok(()));
+            mint_to(&mut erc721, 1, accounts.django);
+
+            set_sender(accounts.django, 0);
+            assert_eq!(
+                erc721.make_swap_proposal(7, vec![1], Vec::new(), accounts.eve, 1000),
+                Ok(())
+            );
+            psp22::mint(token(), accounts.eve, 1000);
+            psp22::approve(token(), accounts.eve, contract_id(), 1000);
+            // the price is pulled in the token, so no native value is accepted
+            set_sender(accounts.eve, 1000);
+            assert_eq!(erc721.accept_trade(7), Err(Error::CurrencyMismatch));
+            set_sender(accounts.eve, 0);
+            assert_eq!(erc721.accept_trade(7), Ok(()));
+            assert_eq!(erc721.owner_of(1), Some(accounts.eve));
+            assert_eq!(psp22::balance_of(token(), accounts.eve), 0);
+            assert_eq!(psp22::balance_of(token(), accounts.charlie), 100);
+            assert_eq!(psp22::balance_of(token(), accounts.django), 850);
+            assert_eq!(psp22::balance_of(token(), contract_id()), 50);
+            assert_eq!(erc721.accrued_token_fees(token()), 50);
+        }
+
+        /// Leaves the contract the way an upgrade from storage version 1 finds it:
+        /// no stored version, and the fields of version 2 pointing at storage cells
+        /// that were never written.
+        fn reset_to_v1(erc721: &mut Erc721) {
+            let mut ptr = KeyPtr::from(Key::from([0x99; 32]));
+            erc721.council = SpreadLayout::pull_spread(&mut ptr);
+            erc721.council_index = SpreadLayout::pull_spread(&mut ptr);
+            erc721.council_count = SpreadLayout::pull_spread(&mut ptr);
+            erc721.council_threshold = SpreadLayout::pull_spread(&mut ptr);
+            erc721.proposals = SpreadLayout::pull_spread(&mut ptr);
+            erc721.proposals_count = SpreadLayout::pull_spread(&mut ptr);
+            erc721.proposal_approvals = SpreadLayout::pull_spread(&mut ptr);
+            erc721.set_templates = SpreadLayout::pull_spread(&mut ptr);
+            erc721.token_discounts = SpreadLayout::pull_spread(&mut ptr);
+            erc721.set_discount_windows = SpreadLayout::pull_spread(&mut ptr);
+            erc721.token_attributes = SpreadLayout::pull_spread(&mut ptr);
+            erc721.token_attribute_keys = SpreadLayout::pull_spread(&mut ptr);
+            erc721.token_attribute_keys_index = SpreadLayout::pull_spread(&mut ptr);
+            erc721.token_attribute_count = SpreadLayout::pull_spread(&mut ptr);
+            erc721.frozen_attributes = SpreadLayout::pull_spread(&mut ptr);
+            erc721.soulbound_sets = SpreadLayout::pull_spread(&mut ptr);
+            erc721.soulbound_tokens = SpreadLayout::pull_spread(&mut ptr);
+            erc721.issuer_merchants = SpreadLayout::pull_spread(&mut ptr);
+            erc721.issuer_merchants_index = SpreadLayout::pull_spread(&mut ptr);
+            erc721.issuer_merchants_count = SpreadLayout::pull_spread(&mut ptr);
+            erc721.set_minted = SpreadLayout::pull_spread(&mut ptr);
+            erc721.burned_tokens = SpreadLayout::pull_spread(&mut ptr);
+            erc721.discount_redemptions = SpreadLayout::pull_spread(&mut ptr);
+            ink_env::clear_contract_storage(&Key::from(STORAGE_VERSION_KEY));
+        }
+
+        #[ink::test]
+        fn migrate_initializes_version_2() {
+            let accounts = accounts();
+            let mut erc721 = deploy();
+            mint_to(&mut erc721, 1, accounts.django);
+            reset_to_v1(&mut erc721);
+            erc721.legacy_set_templates.insert(
+                SET,
+                LegacyTokenTemplate {
+                    name: String::from("legacy"),
+                    image: String::from("ipfs://legacy"),
+                    has_discount: true,
+                    discount: String::from("10% off"),
+                },
+            );
+            assert_eq!(erc721.storage_version(), 1);
+
+            set_sender(accounts.bob, 0);
+            assert_eq!(
+                erc721.mint_batch(SET, vec![(2, template("a"), accounts.django)]),
+                Err(Error::MigrationPending)
+            );
+            assert_eq!(erc721.migrate(), Err(Error::MissingRole));
+            set_sender(accounts.alice, 0);
+            assert_eq!(erc721.migrate(), Ok(()));
+            assert_eq!(erc721.storage_version(), STORAGE_VERSION);
+            assert_eq!(erc721.migrate(), Err(Error::NotAllowed));
+
+            assert_eq!(erc721.council_count(), 0);
+            assert_eq!(erc721.nft_set_minted(SET), 1);
+            assert_eq!(
+                erc721.token_template(SET),
+                Some(TokenTemplate {
+                    name: String::from("legacy"),
+                    image: String::from("ipfs://legacy"),
+                    discount: Some(DiscountTerms {
+                        discount: Discount::Legacy(String::from("10% off")),
+                        valid_from: None,
+                        valid_until: None,
+                        remaining_uses: Some(1),
+                    }),
+                    attributes: Vec::new(),
+                })
+            );
+            // every map of version 2 can be written once migrated
+            set_sender(accounts.bob, 0);
+            assert_eq!(
+                erc721.mint_batch(SET, vec![(2, template("a"), accounts.django)]),
+                Ok(())
+            );
+            assert_eq!(erc721.nft_set_minted(SET), 2);
+            set_sender(accounts.alice, 0);
+            assert_eq!(erc721.set_council(vec![accounts.alice], 1), Ok(()));
+        }
+
+        #[ink::test]
+        fn sale_splits_fee_and_royalty() {
+            let accounts = accounts();
+            let mut erc721 = deploy();
+            set_sender(accounts.alice, 0);
+            assert_eq!(erc721.set_fee(500), Ok(()));
+            set_sender(accounts.bob, 0);
+            assert_eq!(erc721.set_royalty(SET, accounts.charlie, 1000), Ok(()));
+            mint_to(&mut erc721, 1, accounts.django);
+            assert_eq!(erc721.royalty_info(1, 1000), Some((accounts.charlie, 100)));
+
+            set_sender(accounts.django, 0);
+            assert_eq!(
+                erc721.make_swap_proposal(7, vec![1], Vec::new(), accounts.eve, 1000),
+                Ok(())
+            );
+            let royalty_before = balance(accounts.charlie);
+            let seller_before = balance(accounts.django);
+            set_sender(accounts.eve, 1000);
+            assert_eq!(erc721.accept_trade(7), Ok(()));
+            assert_eq!(erc721.owner_of(1), Some(accounts.eve));
+            assert_eq!(erc721.accrued_fees(), 50);
+            assert_eq!(balance(accounts.charlie), royalty_before + 100);
+            assert_eq!(balance(accounts.django), seller_before + 850);
+        }
+
+        #[ink::test]
+        fn accept_trade_only_by_recipient() {
+            let accounts = accounts();
+            let mut erc721 = deploy();
+            mint_to(&mut erc721, 1, accounts.django);
+            set_sender(accounts.django, 0);
+            assert_eq!(
+                erc721.make_swap_proposal(7, vec![1], Vec::new(), accounts.eve, 0),
+                Ok(())
+            );
+            set_sender(accounts.frank, 0);
+            assert_eq!(erc721.accept_trade(7), Err(Error::NotAllowed));
+            assert_eq!(erc721.owner_of(1), Some(accounts.django));
+        }
+
+        #[ink::test]
+        fn swap_id_can_not_be_reused() {
+            let accounts = accounts();
+            let mut erc721 = deploy();
+            mint_to(&mut erc721, 1, accounts.django);
+            mint_to(&mut erc721, 2, accounts.frank);
+            set_sender(accounts.django, 0);
+            assert_eq!(
+                erc721.make_swap_proposal(7, vec![1], Vec::new(), accounts.eve, 1000),
+                Ok(())
+            );
+            // someone else can't swap their own token in for the same price
+            set_sender(accounts.frank, 0);
+            assert_eq!(
+                erc721.make_swap_proposal(7, vec![2], Vec::new(), accounts.eve, 1000),
+                Err(Error::SwapExists)
+            );
+            assert_eq!(erc721.balance_of_bids(accounts.frank), 0);
+            assert_eq!(erc721.balance_of_bids(accounts.django), 1);
+            assert_eq!(erc721.bid_of_owner_by_index(accounts.django, 0), 7);
+
+            set_sender(accounts.eve, 1000);
+            assert_eq!(erc721.accept_trade(7), Ok(()));
+            assert_eq!(erc721.owner_of(1), Some(accounts.eve));
+            assert_eq!(erc721.owner_of(2), Some(accounts.frank));
+            // nor once the swap is done
+            set_sender(accounts.frank, 0);
+            assert_eq!(
+                erc721.make_swap_proposal(7, vec![2], Vec::new(), accounts.eve, 0),
+                Err(Error::SwapExists)
+            );
+        }
+
+        #[ink::test]
+        fn mint_batch_is_atomic() {
+            let accounts = accounts();
+            let mut erc721 = deploy();
+            set_sender(accounts.bob, 0);
+            assert_eq!(
+                erc721.mint_batch(
+                    SET,
+                    vec![
+                        (10, template("a"), accounts.django),
+                        (11, template("b"), accounts.eve),
+                        (10, template("c"), accounts.frank),
+                    ]
+                ),
+                Err(Error::TokenExists)
+            );
+            assert_eq!(
+                erc721.mint_batch(
+                    SET,
+                    vec![
+                        (10, template("a"), accounts.django),
+                        (11, template("b"), AccountId::from([0x0; 32])),
+                    ]
+                ),
+                Err(Error::NotAllowed)
+            );
+            assert_eq!(erc721.owner_of(10), None);
+            assert_eq!(erc721.owner_of(11), None);
+            assert_eq!(erc721.nft_set_balance(SET), 0);
+
+            assert_eq!(
+                erc721.mint_batch(
+                    SET,
+                    vec![
+                        (10, template("a"), accounts.django),
+                        (11, template("b"), accounts.eve),
+                    ]
+                ),
+                Ok(())
+            );
+            assert_eq!(erc721.owner_of(10), Some(accounts.django));
+            assert_eq!(erc721.owner_of(11), Some(accounts.eve));
+            assert_eq!(erc721.nft_set_balance(SET), 2);
+        }
+
+        #[ink::test]
+        fn council_executes_at_threshold() {
+            let accounts = accounts();
+            let mut erc721 = deploy();
+            let members = vec![accounts.alice, accounts.bob, accounts.charlie];
+            set_sender(accounts.alice, 0);
+            assert_eq!(erc721.set_council(members, 2), Ok(()));
+            // admin actions have to go through the council from now on
+            assert_eq!(erc721.set_fee(300), Err(Error::CouncilRequired));
+            assert_eq!(
+                erc721.propose(AdminAction::SetFee { basis_points: 300 }),
+                Ok(())
+            );
+            assert_eq!(erc721.protocol_fee(), 0);
+
+            set_sender(accounts.django, 0);
+            assert_eq!(erc721.approve_proposal(0), Err(Error::NotCouncilMember));
+            set_sender(accounts.alice, 0);
+            assert_eq!(erc721.approve_proposal(0), Err(Error::NotAllowed));
+            assert_eq!(erc721.protocol_fee(), 0);
+
+            set_sender(accounts.bob, 0);
+            assert_eq!(erc721.approve_proposal(0), Ok(()));
+            assert_eq!(erc721.protocol_fee(), 300);
+            assert_eq!(erc721.proposal_approvals(0), 2);
+
+            // executed proposals can not be approved or executed again
+            set_sender(accounts.charlie, 0);
+            assert_eq!(erc721.approve_proposal(0), Err(Error::NotAllowed));
+            assert_eq!(erc721.execute_proposal(0), Err(Error::NotAllowed));
+
+            // role management and upgrades are council actions as well
+            set_sender(accounts.alice, 0);
+            assert_eq!(
+                erc721.grant_role(PAUSER_ROLE, accounts.django),
+                Err(Error::CouncilRequired)
+            );
+            assert_eq!(erc721.upgrade([0x01; 32]), Err(Error::CouncilRequired));
+            assert_eq!(
+                erc721.propose(AdminAction::GrantRole {
+                    role: PAUSER_ROLE,
+                    account: accounts.django,
+                }),
+                Ok(())
+            );
+            assert!(!erc721.has_role(PAUSER_ROLE, accounts.django));
+            set_sender(accounts.charlie, 0);
+            assert_eq!(erc721.approve_proposal(1), Ok(()));
+            assert!(erc721.has_role(PAUSER_ROLE, accounts.django));
+        }
+
+        #[ink::test]
+        fn minter_role_mints_only_into_sets_that_allow_it() {
+            let accounts = accounts();
+            let mut erc721 = deploy();
+            assert!(erc721.has_role(MINTER_ROLE, accounts.alice));
+            set_sender(accounts.alice, 0);
+            assert_eq!(erc721.grant_role(MINTER_ROLE, accounts.django), Ok(()));
+            let mint = |erc721: &mut Erc721, id: TokenId| {
+                set_sender(accounts.django, 0);
+                erc721.mint_batch(SET, vec![(id, template("a"), accounts.eve)])
+            };
+            assert_eq!(mint(&mut erc721, 1), Err(Error::NotIssuer));
+
+            set_sender(accounts.django, 0);
+            assert_eq!(erc721.set_role_minting(SET, true), Err(Error::NotIssuer));
+            set_sender(accounts.bob, 0);
+            assert_eq!(erc721.set_role_minting(SET, true), Ok(()));
+            assert!(erc721.role_minting(SET));
+            assert_eq!(mint(&mut erc721, 1), Ok(()));
+            // the token is issued by the owner of the set
+            assert_eq!(erc721.get_token(1).4, Some(accounts.bob));
+
+            // a new owner has to allow the role again
+            set_sender(accounts.alice, 0);
+            assert_eq!(
+                erc721.set_issuer(accounts.charlie, String::from("charlie"), true),
+                Ok(())
+            );
+            set_sender(accounts.bob, 0);
+            assert_eq!(erc721.transfer_set(SET, accounts.charlie), Ok(()));
+            assert!(!erc721.role_minting(SET));
+            assert_eq!(mint(&mut erc721, 2), Err(Error::NotIssuer));
+        }
+
+        #[ink::test]
+        fn set_sale_validates_window_and_supply() {
+            let accounts = accounts();
+            let mut erc721 = deploy();
+            set_sender(accounts.bob, 0);
+            assert_eq!(
+                erc721.set_sale(SET, 10, 5, 5, now() + 10, now() + 10),
+                Err(Error::InvalidSaleWindow)
+            );
+            assert_eq!(erc721.get_sale(SET), None);
+            open_sale(&mut erc721, 10, 5, 5, now(), now() + 1000);
+            set_sender(accounts.django, 20);
+            assert_eq!(erc721.purchase(SET, 2), Ok(()));
+            set_sender(accounts.bob, 0);
+            assert_eq!(
+                erc721.set_sale(SET, 10, 1, 5, now(), now() + 1000),
+                Err(Error::SupplyExceeded)
+            );
+            assert_eq!(erc721.set_sale(SET, 10, 2, 5, now(), now() + 1000), Ok(()));
+            assert_eq!(erc721.get_sale(SET).map(|sale| sale.sold), Some(2));
+        }
+
+        #[ink::test]
+        fn purchase_respects_window_supply_and_limits() {
+            let accounts = accounts();
+            let mut erc721 = deploy();
+            open_sale(&mut erc721, 10, 3, 200, now() + 1000, now() + 2000);
+            set_sender(accounts.django, 10);
+            assert_eq!(erc721.purchase(SET, 1), Err(Error::SaleNotActive));
+
+            open_sale(&mut erc721, 10, 3, 200, now(), now() + 1);
+            set_sender(accounts.django, 0);
+            assert_eq!(erc721.purchase(SET, 0), Err(Error::InvalidQuantity));
+            assert_eq!(erc721.purchase(SET, 101), Err(Error::BatchTooLarge));
+            set_sender(accounts.django, 15);
+            assert_eq!(erc721.purchase(SET, 2), Err(Error::IncorrectPayment));
+            let issuer_before = balance(accounts.bob);
+            set_sender(accounts.django, 20);
+            assert_eq!(erc721.purchase(SET, 2), Ok(()));
+            assert_eq!(erc721.balance_of(accounts.django), 2);
+            assert_eq!(erc721.purchased(SET, accounts.django), 2);
+            assert_eq!(balance(accounts.bob), issuer_before + 20);
+            set_sender(accounts.eve, 20);
+            assert_eq!(erc721.purchase(SET, 2), Err(Error::SoldOut));
+
+            open_sale(&mut erc721, 10, 10, 3, now(), now() + 1);
+            set_sender(accounts.django, 20);
+            assert_eq!(erc721.purchase(SET, 2), Err(Error::WalletLimitReached));
+            set_sender(accounts.django, 10);
+            assert_eq!(erc721.purchase(SET, 1), Ok(()));
+
+            test::advance_block::<DefaultEnvironment>().expect("Cannot advance block");
+            set_sender(accounts.eve, 10);
+            assert_eq!(erc721.purchase(SET, 1), Err(Error::SaleNotActive));
+        }
+
+        fn leaf(account: AccountId) -> [u8; 32] {
+            let mut leaf = [0u8; 32];
+            ink_env::hash_encoded::<Blake2x256, _>(&account, &mut leaf);
+            leaf
+        }
+
+        /// Hashes two nodes of an allowlist tree, the smaller one first.
+        fn node(a: [u8; 32], b: [u8; 32]) -> [u8; 32] {
+            let (first, second) = if a <= b { (a, b) } else { (b, a) };
+            let mut pair = [0u8; 64];
+            pair[..32].copy_from_slice(&first);
+            pair[32..].copy_from_slice(&second);
+            let mut node = [0u8; 32];
+            ink_env::hash_bytes::<Blake2x256>(&pair, &mut node);
+            node
+        }
+
+        #[ink::test]
+        fn allowlist_mint_verifies_proofs() {
+            let accounts = accounts();
+            let mut erc721 = deploy();
+            // allowlist of django, eve and frank
+            let django = leaf(accounts.django);
+            let eve = leaf(accounts.eve);
+            let frank = leaf(accounts.frank);
+            let root = node(node(django, eve), frank);
+            set_sender(accounts.bob, 0);
+            assert_eq!(erc721.set_allowlist(SET, Some(Hash::from(root))), Ok(()));
+            // early access, the public sale only opens later
+            open_sale(&mut erc721, 10, 10, 2, now() + 1000, now() + 2000);
+
+            set_sender(accounts.django, 10);
+            assert_eq!(erc721.purchase(SET, 1), Err(Error::SaleNotActive));
+            let django_proof = vec![Hash::from(eve), Hash::from(frank)];
+            assert_eq!(erc721.allowlist_mint(SET, 1, django_proof.clone()), Ok(()));
+            set_sender(accounts.eve, 10);
+            let eve_proof = vec![Hash::from(django), Hash::from(frank)];
+            assert_eq!(erc721.allowlist_mint(SET, 1, eve_proof), Ok(()));
+            set_sender(accounts.frank, 10);
+            let frank_proof = vec![Hash::from(node(django, eve))];
+            assert_eq!(erc721.allowlist_mint(SET, 1, frank_proof.clone()), Ok(()));
+            assert_eq!(erc721.allowlist_claimed(SET, accounts.frank), 1);
+
+            // proofs of other accounts, or of accounts not on the list, are rejected
+            set_sender(accounts.eve, 10);
+            assert_eq!(
+                erc721.allowlist_mint(SET, 1, django_proof),
+                Err(Error::NotAllowed)
+            );
+            set_sender(accounts.charlie, 10);
+            assert_eq!(
+                erc721.allowlist_mint(SET, 1, frank_proof),
+                Err(Error::NotAllowed)
+            );
+            assert_eq!(
+                erc721.allowlist_mint(SET, 1, Vec::new()),
+                Err(Error::NotAllowed)
+            );
+            assert_eq!(erc721.balance_of(accounts.charlie), 0);
+        }
+
+        #[ink::test]
+        fn allowlist_and_sale_share_the_wallet_limit() {
+            let accounts = accounts();
+            let mut erc721 = deploy();
+            let (django, eve) = (leaf(accounts.django), leaf(accounts.eve));
+            set_sender(accounts.bob, 0);
+            let root = Hash::from(node(django, eve));
+            assert_eq!(erc721.set_allowlist(SET, Some(root)), Ok(()));
+            open_sale(&mut erc721, 10, 10, 2, now(), now() + 1000);
+
+            set_sender(accounts.django, 20);
+            assert_eq!(erc721.allowlist_mint(SET, 2, vec![Hash::from(eve)]), Ok(()));
+            set_sender(accounts.django, 10);
+            assert_eq!(erc721.purchase(SET, 1), Err(Error::WalletLimitReached));
+
+            set_sender(accounts.eve, 10);
+            assert_eq!(erc721.purchase(SET, 1), Ok(()));
+            set_sender(accounts.eve, 20);
+            assert_eq!(
+                erc721.allowlist_mint(SET, 2, vec![Hash::from(django)]),
+                Err(Error::WalletLimitReached)
+            );
+            set_sender(accounts.eve, 10);
+            assert_eq!(
+                erc721.allowlist_mint(SET, 1, vec![Hash::from(django)]),
+                Ok(())
+            );
+            assert_eq!(erc721.balance_of(accounts.eve), 2);
+        }
+
+        /// Account of the secp256k1 key that signs the vouchers below.
+        const VOUCHER_ISSUER: [u8; 32] = [
+            0x10, 0xe6, 0x04, 0x8b, 0x4c, 0x7e, 0xbb, 0x0c, 0xda, 0xba, 0xd1, 0x98, 0x3d,
+            0x40, 0x1c, 0x68, 0xb9, 0x1c, 0xc8, 0x4e, 0xf3, 0x4f, 0x34, 0x27, 0xdf, 0xc1,
+            0x71, 0xe0, 0xef, 0xa2, 0x99, 0xc6,
+        ];
+
+        /// Signature of the issuer over `voucher(1, None, Timestamp::MAX)`.
+        const VOUCHER_1: [u8; 65] = [
+            0x6b, 0x6c, 0x12, 0x06, 0x65, 0x8d, 0xfb, 0x90, 0xcf, 0xf1, 0x0e, 0xca, 0x22,
+            0xa4, 0x09, 0x50, 0xb3, 0xdc, 0x4b, 0x6f, 0x9b, 0xe9, 0x92, 0x2e, 0x3e, 0xa7,
+            0x4e, 0xd9, 0x9c, 0xba, 0x52, 0x20, 0x4a, 0xf7, 0x10, 0xd6, 0x29, 0xe6, 0x0b,
+            0x0f, 0xc2, 0x1d, 0xee, 0x28, 0x7e, 0x33, 0x7a, 0x6b, 0xd0, 0x55, 0xfe, 0x68,
+            0x58, 0x70, 0x21, 0x6c, 0xe7, 0x67, 0xc2, 0xda, 0x8e, 0x41, 0xac, 0xbe, 0x01,
+        ];
+
+        /// Signature of the issuer over `voucher(2, Some(django), Timestamp::MAX)`.
+        const VOUCHER_2: [u8; 65] = [
+            0x64, 0xe6, 0xb7, 0x39, 0xbf, 0x88, 0x01, 0x4c, 0xcc, 0xd8, 0xb1, 0x1b, 0xf3,
+            0x01, 0x61, 0xde, 0x3a, 0x8f, 0x22, 0x73, 0x67, 0x58, 0x98, 0x61, 0x35, 0xf0,
+            0xd9, 0x83, 0x35, 0x2a, 0x9f, 0xe3, 0x16, 0x97, 0x30, 0xeb, 0xe9, 0xbc, 0xa1,
+            0x78, 0x1e, 0x37, 0xd4, 0xb4, 0x96, 0xeb, 0xe3, 0xed, 0xdf, 0x54, 0x63, 0xcd,
+            0x6e, 0xc5, 0xbc, 0xcd, 0x1e, 0x3a, 0x1f, 0x6a, 0xf1, 0x17, 0xff, 0x4a, 0x01,
+        ];
+
+        /// Signature of the issuer over `voucher(3, None, 0)`.
+        const VOUCHER_3: [u8; 65] = [
+            0x60, 0xcf, 0x3c, 0x53, 0xde, 0x83, 0x5e, 0x31, 0x2f, 0x8f, 0xd8, 0xca, 0xd3,
+            0x93, 0x5b, 0x4d, 0x55, 0x10, 0x7a, 0x1f, 0x14, 0xa5, 0xcc, 0x69, 0xb0, 0x2c,
+            0x85, 0x36, 0xec, 0x06, 0x63, 0x43, 0x57, 0x22, 0xb1, 0x98, 0x52, 0x21, 0x53,
+            0xe1, 0x47, 0x24, 0x56, 0xfc, 0x06, 0xb9, 0x91, 0x4b, 0x03, 0xdd, 0xe3, 0x18,
+            0x58, 0x79, 0x07, 0xfa, 0x56, 0x72, 0xb4, 0x3e, 0xd8, 0x08, 0x04, 0xc4, 0x00,
+        ];
+
+        /// Signature of another key over `voucher(4, None, Timestamp::MAX)`.
+        const VOUCHER_4_OTHER_SIGNER: [u8; 65] = [
+            0x60, 0xa0, 0x53, 0x45, 0xfd, 0x71, 0x50, 0x54, 0x50, 0x6f, 0x78, 0xcb, 0xfc,
+            0x3d, 0x53, 0xad, 0xba, 0x16, 0x35, 0x27, 0x99, 0xf2, 0xbf, 0x18, 0xa7, 0x81,
+            0xa9, 0x99, 0x70, 0x62, 0x7b, 0x8c, 0x34, 0x90, 0xb7, 0x9d, 0xc8, 0xa8, 0x74,
+            0xed, 0xe9, 0x36, 0xba, 0x18, 0x7d, 0xb0, 0xd3, 0xae, 0x73, 0x6c, 0x70, 0x21,
+            0x68, 0xdf, 0xdb, 0xc6, 0x59, 0xd7, 0x47, 0x60, 0xef, 0x58, 0x85, 0x2a, 0x00,
+        ];
+
+        /// Voucher of token `id` in `SET` for 100 units of its currency. The signatures
+        /// above are made for the default contract account of the test environment.
+        fn voucher(
+            id: TokenId,
+            recipient: Option<AccountId>,
+            expires_at: Timestamp,
+        ) -> Voucher {
+            Voucher {
+                setid: SET,
+                id,
+                metadata: template("voucher"),
+                price: 100,
+                recipient,
+                expires_at,
+            }
+        }
+
+        #[ink::test]
+        fn redeem_voucher_verifies_signer_replay_expiry_and_recipient() {
+            let accounts = accounts();
+            assert_eq!(contract_id(), AccountId::from([0x07; 32]));
+            let mut erc721 = deploy();
+            use_psp22(&mut erc721);
+            let issuer = AccountId::from(VOUCHER_ISSUER);
+            set_sender(accounts.alice, 0);
+            assert_eq!(
+                erc721.set_issuer(issuer, String::from("issuer"), true),
+                Ok(())
+            );
+            set_sender(accounts.bob, 0);
+            assert_eq!(erc721.transfer_set(SET, issuer), Ok(()));
+            for buyer in [accounts.django, accounts.eve].iter() {
+                psp22::mint(token(), *buyer, 100);
+                psp22::approve(token(), *buyer, contract_id(), 100);
+            }
+
+            // changed vouchers and vouchers of other keys recover another signer
+            set_sender(accounts.eve, 0);
+            let mut changed = voucher(1, None, Timestamp::MAX);
+            changed.price = 0;
+            assert_eq!(
+                erc721.redeem_voucher(changed, VOUCHER_1),
+                Err(Error::InvalidSignature)
+            );
+            assert_eq!(
+                erc721.redeem_voucher(
+                    voucher(4, None, Timestamp::MAX),
+                    VOUCHER_4_OTHER_SIGNER
+                ),
+                Err(Error::InvalidSignature)
+            );
+            assert_eq!(erc721.owner_of(4), None);
+
+            let first = voucher(1, None, Timestamp::MAX);
+            assert_eq!(erc721.redeem_voucher(first.clone(), VOUCHER_1), Ok(()));
+            assert_eq!(erc721.owner_of(1), Some(accounts.eve));
+            assert_eq!(psp22::balance_of(token(), issuer), 100);
+            let mut hash = [0u8; 32];
+            ink_env::hash_encoded::<Blake2x256, _>(&(contract_id(), &first), &mut hash);
+            assert_eq!(
+                erc721.voucher_redeemer(Hash::from(hash)),
+                Some(accounts.eve)
+            );
+
+            // a redeemed voucher can't be redeemed again, not even by someone else
+            set_sender(accounts.django, 0);
+            assert_eq!(
+                erc721.redeem_voucher(first, VOUCHER_1),
+                Err(Error::VoucherRedeemed)
+            );
+            assert_eq!(
+                erc721.redeem_voucher(voucher(3, None, 0), VOUCHER_3),
+                Err(Error::VoucherExpired)
+            );
+
+            // only the recipient can redeem a voucher made out to it
+            let second = voucher(2, Some(accounts.django), Timestamp::MAX);
+            set_sender(accounts.eve, 0);
+            assert_eq!(
+                erc721.redeem_voucher(second.clone(), VOUCHER_2),
+                Err(Error::NotAllowed)
+            );
+            set_sender(accounts.django, 0);
+            assert_eq!(erc721.redeem_voucher(second, VOUCHER_2), Ok(()));
+            assert_eq!(erc721.owner_of(2), Some(accounts.django));
+            assert_eq!(psp22::balance_of(token(), issuer), 200);
+            assert_eq!(erc721.owner_of(3), None);
+        }
+
+        /// Account of the secp256k1 key of a merchant co-signing discount redemptions.
+        const MERCHANT: [u8; 32] = [
+            0x3d, 0xca, 0x7a, 0xfc, 0x84, 0x92, 0x10, 0x88, 0x39, 0xbc, 0xa3, 0x98, 0x45,
+            0xf7, 0xcd, 0x12, 0xf9, 0x2a, 0xc7, 0x2c, 0x79, 0x3d, 0xce, 0x56, 0x63, 0x27,
+            0x4b, 0x0f, 0x86, 0x6e, 0x5e, 0xbc,
+        ];
+
+        /// Signature of the merchant over `(contract, 1, 1)`, for the redemption of
+        /// token 1 at nonce 1.
+        const DISCOUNT_NONCE_1: [u8; 65] = [
+            0xdf, 0xf1, 0xa0, 0x52, 0x24, 0x5b, 0x78, 0xc8, 0xe6, 0x88, 0x25, 0x67, 0xcd,
+            0x64, 0xdb, 0x67, 0x73, 0x69, 0x0e, 0x42, 0x41, 0x86, 0x6b, 0xae, 0x66, 0xa9,
+            0x79, 0x8b, 0xba, 0x9a, 0x7c, 0x35, 0x1f, 0x51, 0xae, 0x87, 0x60, 0x99, 0x17,
+            0x33, 0xd4, 0x22, 0xfc, 0x8e, 0x74, 0x44, 0x17, 0xd8, 0x1f, 0x5e, 0x80, 0xf1,
+            0xab, 0x63, 0x66, 0xc1, 0xc1, 0xa2, 0x3d, 0x66, 0x8a, 0xa5, 0x50, 0x7c, 0x01,
+        ];
+
+        /// Signature of the merchant over `(contract, 1, 2)`.
+        const DISCOUNT_NONCE_2: [u8; 65] = [
+            0xfa, 0xeb, 0xd9, 0xd4, 0x71, 0xc6, 0x58, 0xaf, 0xc1, 0x0b, 0x8f, 0x37, 0x02,
+            0x7e, 0x35, 0x2e, 0xf8, 0x47, 0x48, 0xcf, 0x30, 0xef, 0xb6, 0xd9, 0xab, 0xfb,
+            0xd5, 0x27, 0x2c, 0x82, 0x59, 0x6b, 0x35, 0x8a, 0x5c, 0xd0, 0x44, 0x1f, 0xfb,
+            0x53, 0x38, 0x6a, 0x4b, 0x87, 0xf0, 0xde, 0x68, 0x5d, 0xaa, 0x4a, 0xaa, 0xa6,
+            0xb9, 0x07, 0xad, 0xd4, 0xa9, 0x7f, 0x64, 0x22, 0x06, 0xb9, 0xc4, 0x3e, 0x00,
+        ];
+
+        #[ink::test]
+        fn redeem_discount_by_merchants_and_cosigning_holders() {
+            let accounts = accounts();
+            assert_eq!(contract_id(), AccountId::from([0x07; 32]));
+            let mut erc721 = deploy();
+            set_sender(accounts.bob, 0);
+            let terms = DiscountTerms {
+                discount: Discount::Percentage { basis_points: 1000 },
+                valid_from: None,
+                valid_until: None,
+                remaining_uses: Some(3),
+            };
+            assert_eq!(
+                erc721.mint(
+                    SET,
+                    1,
+                    String::from("token"),
+                    String::from("ipfs://image"),
+                    Some(terms),
+                    Vec::new(),
+                    accounts.django,
+                    0
+                ),
+                Ok(())
+            );
+            assert_eq!(erc721.add_merchant(accounts.charlie), Ok(()));
+            assert_eq!(erc721.add_merchant(AccountId::from(MERCHANT)), Ok(()));
+
+            // only merchants of the issuer redeem without a co-signature
+            set_sender(accounts.eve, 0);
+            assert_eq!(erc721.redeem_discount(1, None), Err(Error::NotMerchant));
+            set_sender(accounts.charlie, 0);
+            assert_eq!(erc721.redeem_discount(1, None), Ok(()));
+            assert_eq!(erc721.discount_nonce(1), 1);
+
+            // the holder redeems with a co-signature over the current nonce
+            set_sender(accounts.eve, 0);
+            assert_eq!(
+                erc721.redeem_discount(1, Some(DISCOUNT_NONCE_1)),
+                Err(Error::NotOwner)
+            );
+            set_sender(accounts.django, 0);
+            assert_eq!(
+                erc721.redeem_discount(1, Some(DISCOUNT_NONCE_2)),
+                Err(Error::NotMerchant)
+            );
+            assert_eq!(erc721.redeem_discount(1, Some(DISCOUNT_NONCE_1)), Ok(()));
+            // the nonce moved on, so the co-signature no longer recovers the merchant
+            assert_eq!(
+                erc721.redeem_discount(1, Some(DISCOUNT_NONCE_1)),
+                Err(Error::NotMerchant)
+            );
+            let remaining_uses = |erc721: &Erc721| {
+                erc721.discount_of(1).and_then(|terms| terms.remaining_uses)
+            };
+            assert_eq!(remaining_uses(&erc721), Some(1));
+            assert_eq!(erc721.redeem_discount(1, Some(DISCOUNT_NONCE_2)), Ok(()));
+            assert_eq!(remaining_uses(&erc721), Some(0));
+
+            set_sender(accounts.charlie, 0);
+            assert_eq!(erc721.redeem_discount(1, None), Err(Error::DiscountUsedUp));
+            assert_eq!(erc721.discount_nonce(1), 3);
+        }
+
+        #[ink::test]
+        fn pause_stops_minting_transfers_and_swaps() {
+            let accounts = accounts();
+            let mut erc721 = deploy();
+            mint_to(&mut erc721, 1, accounts.django);
+            mint_to(&mut erc721, 2, accounts.eve);
+            set_sender(accounts.django, 0);
+            assert_eq!(
+                erc721.make_swap_proposal(7, vec![1], vec![2], accounts.eve, 0),
+                Ok(())
+            );
+            set_sender(accounts.alice, 0);
+            assert_eq!(erc721.pause(), Ok(()));
+            assert!(erc721.paused());
+
+            set_sender(accounts.bob, 0);
+            assert_eq!(
+                erc721.mint(
+                    SET,
+                    3,
+                    String::from("token"),
+                    String::from("ipfs://image"),
+                    None,
+                    Vec::new(),
+                    accounts.django,
+                    0
+                ),
+                Err(Error::ContractPaused)
+            );
+            set_sender(accounts.django, 0);
+            assert_eq!(
+                erc721.transfer(accounts.frank, 1),
+                Err(Error::ContractPaused)
+            );
+            assert_eq!(
+                erc721.make_swap_proposal(8, vec![1], Vec::new(), accounts.frank, 0),
+                Err(Error::ContractPaused)
+            );
+            set_sender(accounts.eve, 0);
+            assert_eq!(erc721.accept_trade(7), Err(Error::ContractPaused));
+            assert_eq!(erc721.owner_of(1), Some(accounts.django));
+            assert_eq!(erc721.owner_of(3), None);
+            // pending proposals can still be called off
+            assert_eq!(erc721.reject_trade(7), Ok(()));
+            assert_eq!(erc721.balance_of_bids(accounts.django), 0);
+
+            set_sender(accounts.alice, 0);
+            assert_eq!(erc721.unpause(), Ok(()));
+            assert!(!erc721.paused());
+            set_sender(accounts.django, 0);
+            assert_eq!(erc721.transfer(accounts.frank, 1), Ok(()));
+        }
+
+        #[ink::test]
+        fn burn_updates_enumeration() {
+            let accounts = accounts();
+            let mut erc721 = deploy();
+            mint_to(&mut erc721, 1, accounts.django);
+            mint_to(&mut erc721, 2, accounts.django);
+            mint_to(&mut erc721, 3, accounts.django);
+            assert_eq!(erc721.balance_of(accounts.django), 3);
+            assert_eq!(erc721.nft_set_balance(SET), 3);
+
+            set_sender(accounts.eve, 0);
+            assert_eq!(erc721.burn(1), Err(Error::NotOwner));
+            set_sender(accounts.django, 0);
+            assert_eq!(erc721.burn(1), Ok(()));
+            assert_eq!(erc721.owner_of(1), None);
+            assert_eq!(erc721.get_token(1).0, None);
+            assert_eq!(erc721.balance_of(accounts.django), 2);
+            assert_eq!(erc721.nft_set_balance(SET), 2);
+            // the last token takes the place of the burned one
+            assert_eq!(erc721.token_of_owner_by_index(accounts.django, 0), 3);
+            assert_eq!(erc721.token_of_owner_by_index(accounts.django, 1), 2);
+            assert_eq!(erc721.nft_by_set_index(SET, 0), 3);
+            assert_eq!(erc721.nft_by_set_index(SET, 1), 2);
+            assert_eq!(erc721.nft_by_set_index(SET, 2), 0);
+
+            assert_eq!(erc721.burn(3), Ok(()));
+            assert_eq!(erc721.burn(2), Ok(()));
+            assert_eq!(erc721.balance_of(accounts.django), 0);
+            assert_eq!(erc721.nft_set_balance(SET), 0);
+            assert_eq!(erc721.burn(2), Err(Error::NotOwner));
+            // burned ids stay taken and keep counting against the supply
+            assert_eq!(erc721.nft_set_minted(SET), 3);
+            set_sender(accounts.bob, 0);
+            assert_eq!(
+                erc721.mint_batch(SET, vec![(1, template("a"), accounts.django)]),
+                Err(Error::TokenExists)
+            );
+            assert_eq!(
+                erc721.set_max_supply(SET, Some(2)),
+                Err(Error::SupplyExceeded)
+            );
+            assert_eq!(erc721.set_max_supply(SET, Some(4)), Ok(()));
+            mint_to(&mut erc721, 4, accounts.django);
+            assert_eq!(
+                erc721.mint_batch(SET, vec![(5, template("e"), accounts.django)]),
+                Err(Error::SupplyExceeded)
+            );
+        }
+
+        #[ink::test]
+        fn soulbound_tokens_can_only_be_revoked() {
+            let accounts = accounts();
+            let mut erc721 = deploy();
+            mint_to(&mut erc721, 1, accounts.django);
+            mint_to(&mut erc721, 2, accounts.django);
+            mint_to(&mut erc721, 3, accounts.eve);
+            set_sender(accounts.bob, 0);
+            // only soulbound tokens can be taken back by their issuer
+            assert_eq!(erc721.revoke_token(1), Err(Error::NotAllowed));
+            assert_eq!(erc721.set_token_soulbound(1, true), Ok(()));
+
+            set_sender(accounts.django, 0);
+            assert_eq!(erc721.transfer(accounts.frank, 1), Err(Error::Soulbound));
+            assert_eq!(
+                erc721.transfer_batch(vec![(accounts.frank, 2), (accounts.frank, 1)]),
+                Err(Error::Soulbound)
+            );
+            assert_eq!(erc721.owner_of(2), Some(accounts.django));
+            assert_eq!(
+                erc721.make_swap_proposal(7, vec![1], Vec::new(), accounts.eve, 0),
+                Err(Error::Soulbound)
+            );
+            set_sender(accounts.eve, 0);
+            assert_eq!(
+                erc721.make_swap_proposal(8, vec![3], vec![1], accounts.django, 0),
+                Err(Error::Soulbound)
+            );
+            assert_eq!(erc721.balance_of_bids(accounts.eve), 0);
+
+            // soulbound sets bind every token in them
+            set_sender(accounts.bob, 0);
+            assert_eq!(erc721.set_soulbound(SET, true), Ok(()));
+            set_sender(accounts.eve, 0);
+            assert_eq!(erc721.transfer(accounts.frank, 3), Err(Error::Soulbound));
+            set_sender(accounts.bob, 0);
+            assert_eq!(erc721.set_soulbound(SET, false), Ok(()));
+            set_sender(accounts.django, 0);
+            assert_eq!(erc721.transfer(accounts.frank, 2), Ok(()));
+
+            set_sender(accounts.eve, 0);
+            assert_eq!(erc721.revoke_token(1), Err(Error::NotIssuer));
+            set_sender(accounts.bob, 0);
+            assert_eq!(erc721.revoke_token(1), Ok(()));
+            assert_eq!(erc721.owner_of(1), None);
+            assert_eq!(erc721.balance_of(accounts.django), 0);
+        }
+    }
 }
 
 // /// Unit tests